@@ -1,5 +1,182 @@
 fn main() {
-    println!("cargo:rustc-link-search=/usr/local/lib");
-    println!("cargo:rustc-link-lib=static=GKlib");
-    println!("cargo:rustc-link-lib=static=metis");
-}
\ No newline at end of file
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(all(feature = "vendored", not(feature = "native")))]
+    vendored::build();
+
+    #[cfg(not(any(feature = "vendored", feature = "native")))]
+    system::link();
+}
+
+#[cfg(not(any(feature = "vendored", feature = "native")))]
+mod system {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// The bindings in `src/sys.rs` follow the option numbering of METIS 5.2, which
+    /// differs from earlier releases, so an older library would misread options.
+    const MIN_VERSION: (u32, u32) = (5, 2);
+
+    /// Link against an installed METIS, looking in `METIS_LIB_DIR`, then pkg-config,
+    /// then `/usr/local/lib`, and finally leaving it to the system linker paths.
+    ///
+    /// The version is checked through pkg-config, or through `metis.h` in
+    /// `METIS_INCLUDE_DIR` or next to the library otherwise. When neither is
+    /// available the library is linked unchecked, and it must be METIS 5.2.x.
+    pub fn link() {
+        println!("cargo:rerun-if-env-changed=METIS_LIB_DIR");
+        println!("cargo:rerun-if-env-changed=METIS_INCLUDE_DIR");
+        println!("cargo:rerun-if-env-changed=METIS_STATIC");
+
+        if let Some(dir) = env::var_os("METIS_LIB_DIR") {
+            let dir = PathBuf::from(dir);
+            check_header(&dir);
+            let kind = if env::var_os("METIS_STATIC").is_some() { "static" } else { "dylib" };
+            println!("cargo:rustc-link-search=native={}", dir.display());
+            link_gklib_if_separate(&dir, kind);
+            println!("cargo:rustc-link-lib={}=metis", kind);
+            return;
+        }
+
+        let min = format!("{}.{}", MIN_VERSION.0, MIN_VERSION.1);
+        if pkg_config::Config::new().atleast_version(&min).probe("metis").is_ok() {
+            return;
+        }
+        if let Ok(lib) = pkg_config::Config::new().cargo_metadata(false).probe("metis") {
+            panic!(
+                "pkg-config found METIS {}, but metis-rs needs METIS {} or later",
+                lib.version, min
+            );
+        }
+
+        let local = Path::new("/usr/local/lib");
+        if local.join("libmetis.a").exists() {
+            check_header(local);
+            println!("cargo:rustc-link-search=native={}", local.display());
+            link_gklib_if_separate(local, "static");
+            println!("cargo:rustc-link-lib=static=metis");
+            return;
+        }
+
+        println!("cargo:rustc-link-lib=metis");
+    }
+
+    /// Read `METIS_VER_*` from the `metis.h` that goes with the library in `lib_dir`
+    /// and refuse anything older than `MIN_VERSION`.
+    fn check_header(lib_dir: &Path) {
+        let include = env::var_os("METIS_INCLUDE_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| lib_dir.parent().unwrap_or(lib_dir).join("include"));
+        let header = include.join("metis.h");
+        let Ok(text) = fs::read_to_string(&header) else {
+            println!(
+                "cargo:warning=metis.h not found in {}; cannot check the METIS version",
+                include.display()
+            );
+            return;
+        };
+        println!("cargo:rerun-if-changed={}", header.display());
+
+        let define = |name: &str| {
+            text.lines()
+                .filter_map(|line| line.trim().strip_prefix("#define"))
+                .filter_map(|rest| rest.trim().strip_prefix(name))
+                .find_map(|value| value.trim().parse::<u32>().ok())
+                .unwrap_or_else(|| panic!("{} not defined in {}", name, header.display()))
+        };
+        let version = (define("METIS_VER_MAJOR"), define("METIS_VER_MINOR"));
+        if version < MIN_VERSION {
+            panic!(
+                "{} is METIS {}.{}, but metis-rs needs METIS {}.{} or later",
+                header.display(),
+                version.0,
+                version.1,
+                MIN_VERSION.0,
+                MIN_VERSION.1
+            );
+        }
+    }
+
+    /// METIS 5.2 builds GKlib as its own library, while older releases and most
+    /// distribution packages fold it into libmetis.
+    fn link_gklib_if_separate(dir: &Path, kind: &str) {
+        if ["libGKlib.a", "libGKlib.so", "libGKlib.dylib"].iter().any(|f| dir.join(f).exists()) {
+            println!("cargo:rustc-link-lib={}=GKlib", kind);
+        }
+    }
+}
+
+#[cfg(all(feature = "vendored", not(feature = "native")))]
+mod vendored {
+    use std::env;
+    use std::path::{Path, PathBuf};
+
+    /// The sources bundled in `vendor/`, unless `var` points elsewhere.
+    fn source_dir(var: &str, default: &str) -> PathBuf {
+        println!("cargo:rerun-if-env-changed={}", var);
+        let dir = env::var_os(var).map(PathBuf::from).unwrap_or_else(|| {
+            Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("vendor").join(default)
+        });
+
+        if !dir.is_dir() {
+            panic!("vendored {} sources not found at {}; check {}", default, dir.display(), var);
+        }
+
+        println!("cargo:rerun-if-changed={}", dir.display());
+        dir
+    }
+
+    fn c_files(dir: &Path, skip: &[&str]) -> Vec<PathBuf> {
+        let mut files = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "c"))
+            .filter(|path| !skip.iter().any(|s| path.file_name().unwrap() == *s))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    pub fn build() {
+        let gklib = source_dir("GKLIB_SRC_DIR", "GKlib");
+        let metis = source_dir("METIS_SRC_DIR", "METIS");
+        let windows = env::var("CARGO_CFG_TARGET_OS").unwrap() == "windows";
+
+        let mut base = cc::Build::new();
+        base.include(&gklib)
+            .define("NDEBUG", None)
+            .define("NDEBUG2", None)
+            .define("IDXTYPEWIDTH", "32")
+            .define("REALTYPEWIDTH", "32")
+            .warnings(false);
+
+        if windows {
+            base.include(gklib.join("win32"))
+                .define("WIN32", None)
+                .define("MSC", None)
+                .define("_CRT_SECURE_NO_DEPRECATE", None)
+                .define("USE_GKREGEX", None)
+                .define("__thread", "__declspec(thread)");
+        } else {
+            base.define("LINUX", None)
+                .define("_FILE_OFFSET_BITS", "64")
+                .define("_GNU_SOURCE", None)
+                .flag_if_supported("-std=c99")
+                .flag_if_supported("-fno-strict-aliasing");
+        }
+
+        let skip: &[&str] = if windows { &[] } else { &["gkregex.c"] };
+        let mut gk = base.clone();
+        gk.files(c_files(&gklib, skip));
+        if windows {
+            gk.files(c_files(&gklib.join("win32"), &[]));
+        }
+        gk.compile("GKlib");
+
+        base.include(metis.join("include"))
+            .include(metis.join("libmetis"))
+            .files(c_files(&metis.join("libmetis"), &[]))
+            .compile("metis");
+    }
+}
@@ -1,3 +1,4 @@
+use metis::GraphLike;
 use rand::Rng;
 
 fn main() {
@@ -7,10 +8,45 @@ fn main() {
     let mgraph = metis::Graph::new_weighted(&wgraph);
     let options = metis::Options::default();
     let sep = mgraph.vertex_separator(&options).unwrap();
+    sep.validate(&wgraph).unwrap();
     println!("{}", sep.cut.len());
 
     let mgraph = metis::Graph::new(&graph);
     let options = metis::Options::default();
     let sep = mgraph.vertex_separator(&options).unwrap();
     println!("{}", sep.cut.len());
+
+    let (seed, sep) = mgraph.best_separator_over_seeds(&[1, 2, 3, 4], &options, |s| s.cut.len()).unwrap();
+    println!("{} {}", seed, sep.cut.len());
+
+    let tgraph = graph.map(|_, _| [1, rng.gen_range(0..2usize)], |_, _| ());
+    let tmgraph = metis::Graph::new_multi_weighted(&tgraph).unwrap();
+    let toptions = metis::Options::default().max_imbalances(vec![1.1, 1.1]);
+    let sep = tmgraph.vertex_separator(&toptions).unwrap();
+    println!("{}", sep.cut.len());
+
+    // The rest needs METIS itself, which the native feature does not link.
+    if cfg!(feature = "native") {
+        return;
+    }
+
+    let part = mgraph.partition(4, &options).unwrap();
+    println!("{} {:?}", part.objective, part.parts().iter().map(|p| p.len()).collect::<Vec<_>>());
+
+    let (order, tree) = mgraph.nested_dissection_tree(4, &options).unwrap();
+    println!("{} {:?} {:?}", order.order.len(), tree.parts, tree.separators);
+
+    let ewgraph = graph.map(|_, _| (), |_, _| rng.gen_range(1..10usize));
+    let mgraph = metis::Graph::new(&ewgraph).with_edge_weights(&ewgraph);
+    let part = mgraph.partition(4, &options).unwrap();
+    println!("{}", part.objective);
+
+    let part = tmgraph.partition(2, &toptions).unwrap();
+    println!("{}", part.objective);
+
+    let options = metis::Options::default();
+    let gadgets = (0..50).map(|_| rand::seq::index::sample(&mut rng, 100, 4).into_vec());
+    let mesh = metis::Mesh::new(gadgets);
+    let part = mesh.partition_dual(2, 1, &options).unwrap();
+    println!("{} {}", part.objective, mesh.dual_graph(1).unwrap().vertices().len());
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+//! Computing many vertex separators at once on several threads, for callers that
+//! want to compare several candidate graphs or several random seeds and keep the
+//! best result. METIS keeps global state that every call touches, so with METIS
+//! the jobs run one at a time; only the native backend runs them in parallel.
+
+use crate::{Error, Graph, GraphLike, Options, VertexSeparator};
+
+/// Compute a separator for each graph and its options, using up to `threads`
+/// threads with the native backend, or one per available core if `threads` is
+/// zero. Results are returned in the order of `jobs`.
+///
+/// With METIS the jobs run one after another on the calling thread whatever
+/// `threads` is, since METIS cannot run two calls at once.
+pub fn separators<G>(jobs: &[(&Graph<G>, Options)], threads: usize) -> Vec<Result<VertexSeparator<G>, Error>>
+where
+    G: GraphLike,
+    G::Vertex: Send + Sync
+{
+    run(jobs, threads, |sep| sep)
+}
+
+/// Compute a separator for each graph and return the index and separator of the
+/// one with the lowest `score`, preferring earlier graphs on ties. Fails with the
+/// first error in the order of `graphs`, or if `graphs` is empty.
+pub fn best_separator<G, S>(
+    graphs: &[&Graph<G>],
+    options: &Options,
+    score: impl Fn(&VertexSeparator<G>) -> S + Sync
+) -> Result<(usize, VertexSeparator<G>), Error>
+where
+    G: GraphLike,
+    G::Vertex: Send + Sync,
+    S: Ord + Send
+{
+    let jobs = graphs.iter().map(|&g| (g, options.clone())).collect::<Vec<_>>();
+    best(&jobs, score)
+}
+
+impl<G> Graph<G>
+where
+    G: GraphLike,
+    G::Vertex: Send + Sync
+{
+    /// Compute a separator of this graph for each seed and return the seed and
+    /// separator with the lowest `score`, preferring earlier seeds on ties.
+    pub fn best_separator_over_seeds<S: Ord + Send>(
+        &self,
+        seeds: &[i32],
+        options: &Options,
+        score: impl Fn(&VertexSeparator<G>) -> S + Sync
+    ) -> Result<(i32, VertexSeparator<G>), Error> {
+        let jobs = seeds.iter().map(|&s| (self, options.clone().seed(s))).collect::<Vec<_>>();
+        best(&jobs, score).map(|(i, sep)| (seeds[i], sep))
+    }
+}
+
+fn best<G, S>(jobs: &[(&Graph<G>, Options)], score: impl Fn(&VertexSeparator<G>) -> S + Sync) -> Result<(usize, VertexSeparator<G>), Error>
+where
+    G: GraphLike,
+    G::Vertex: Send + Sync,
+    S: Ord + Send
+{
+    let mut best: Option<(S, usize, VertexSeparator<G>)> = None;
+    for (i, result) in run(jobs, 0, |sep| (score(&sep), sep)).into_iter().enumerate() {
+        let (s, sep) = result?;
+        if best.as_ref().is_none_or(|(b, _, _)| s < *b) {
+            best = Some((s, i, sep));
+        }
+    }
+
+    best.map(|(_, i, sep)| (i, sep))
+        .ok_or_else(|| Error::InvalidArgument("no graphs to separate".to_string()))
+}
+
+/// Run the jobs on up to `threads` scoped threads, or one per available core if
+/// `threads` is zero, each taking an equal share of consecutive jobs.
+fn run<G, T>(jobs: &[(&Graph<G>, Options)], threads: usize, finish: impl Fn(VertexSeparator<G>) -> T + Sync) -> Vec<Result<T, Error>>
+where
+    G: GraphLike,
+    G::Vertex: Send + Sync,
+    T: Send
+{
+    let separate = |(graph, options): &(&Graph<G>, Options)| graph.vertex_separator(options).map(&finish);
+
+    // Calls into METIS are serialised anyway, so extra threads would only wait.
+    let threads = match threads {
+        _ if !cfg!(feature = "native") => 1,
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n
+    };
+    if threads <= 1 || jobs.len() <= 1 {
+        return jobs.iter().map(separate).collect();
+    }
+
+    let separate = &separate;
+    std::thread::scope(|scope| {
+        jobs.chunks(jobs.len().div_ceil(threads))
+            .map(|chunk| scope.spawn(move || chunk.iter().map(separate).collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[test]
+fn separators_test() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let mut graph = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let nodes = (0..200).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    for a in 0..200 {
+        for b in a + 1..200 {
+            if rng.gen::<f64>() < 0.03 {
+                graph.add_edge(nodes[a], nodes[b], ());
+            }
+        }
+    }
+
+    // Batched separators must match computing each seed on its own, which fails
+    // if concurrent jobs disturb each other's random state.
+    let mgraph = Graph::new(&graph);
+    let jobs = (1..=16).map(|s| (&mgraph, Options::default().seed(s))).collect::<Vec<_>>();
+    for _ in 0..3 {
+        for ((_, options), sep) in jobs.iter().zip(separators(&jobs, 8)) {
+            let sep = sep.unwrap();
+            let serial = mgraph.vertex_separator(options).unwrap();
+            assert_eq!((sep.left, sep.right, sep.cut), (serial.left, serial.right, serial.cut));
+        }
+    }
+}
@@ -1,17 +1,51 @@
 #[allow(nonstandard_style)] 
 pub mod sys;
+pub mod batch;
+#[cfg(feature = "native")]
+mod native;
+pub mod mesh;
+
+pub use mesh::{Mesh, MeshGraph, MeshPartition};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
 
 pub trait GraphLike {
-    type Vertex: Clone;
+    type Vertex: Clone + Eq + Hash + fmt::Debug;
 
     fn vertices(&self) -> Vec<Self::Vertex>;
     fn has_edge(&self, a: Self::Vertex, b: Self::Vertex) -> bool;
+
+    /// The vertices adjacent to `a` in either direction. The default probes every
+    /// vertex with `has_edge`, so implementors should override it when they can
+    /// enumerate neighbours directly.
+    fn neighbors(&self, a: Self::Vertex) -> Vec<Self::Vertex> {
+        self.vertices()
+            .into_iter()
+            .filter(|b| self.has_edge(a.clone(), b.clone()) || self.has_edge(b.clone(), a.clone()))
+            .collect()
+    }
 }
 
 pub trait GraphLikeWeighted: GraphLike {
     fn vertex_weight(&self, v: Self::Vertex) -> usize;
 }
 
+/// Vertices carrying several weights, such as vertex count and T-count, which
+/// partitions and separators keep balanced simultaneously. Every vertex must
+/// report exactly `num_constraints` weights.
+pub trait GraphLikeMultiWeighted: GraphLike {
+    fn num_constraints(&self) -> usize;
+    fn vertex_weights(&self, v: Self::Vertex) -> Vec<usize>;
+}
+
+pub trait GraphLikeEdgeWeighted: GraphLike {
+    /// The cost of cutting the edges between adjacent vertices `a` and `b`.
+    /// METIS requires this to be symmetric in `a` and `b`.
+    fn edge_weight(&self, a: Self::Vertex, b: Self::Vertex) -> usize;
+}
+
 #[cfg(feature = "petgraph")]
 impl<N, E, Ty: petgraph::EdgeType> GraphLike for petgraph::Graph<N, E, Ty> {
     type Vertex = petgraph::graph::NodeIndex;
@@ -23,6 +57,10 @@ impl<N, E, Ty: petgraph::EdgeType> GraphLike for petgraph::Graph<N, E, Ty> {
     fn has_edge(&self, a: Self::Vertex, b: Self::Vertex) -> bool {
         self.contains_edge(a, b)
     }
+
+    fn neighbors(&self, a: Self::Vertex) -> Vec<Self::Vertex> {
+        self.neighbors_undirected(a).collect()
+    }
 }
 
 #[cfg(feature = "petgraph")]
@@ -36,6 +74,10 @@ impl<N, E, Ty: petgraph::EdgeType> GraphLike for petgraph::stable_graph::StableG
     fn has_edge(&self, a: Self::Vertex, b: Self::Vertex) -> bool {
         self.contains_edge(a, b)
     }
+
+    fn neighbors(&self, a: Self::Vertex) -> Vec<Self::Vertex> {
+        self.neighbors_undirected(a).collect()
+    }
 }
 
 #[cfg(feature = "petgraph")]
@@ -52,11 +94,113 @@ impl<N: Clone + Into<usize>, E, Ty: petgraph::EdgeType> GraphLikeWeighted for pe
     }
 }
 
+#[cfg(feature = "petgraph")]
+impl<N: AsRef<[usize]>, E, Ty: petgraph::EdgeType> GraphLikeMultiWeighted for petgraph::Graph<N, E, Ty> {
+    fn num_constraints(&self) -> usize {
+        self.node_weights().next().map_or(1, |w| w.as_ref().len())
+    }
+
+    fn vertex_weights(&self, v: Self::Vertex) -> Vec<usize> {
+        self.node_weight(v).unwrap().as_ref().to_vec()
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<N: AsRef<[usize]>, E, Ty: petgraph::EdgeType> GraphLikeMultiWeighted for petgraph::stable_graph::StableGraph<N, E, Ty> {
+    fn num_constraints(&self) -> usize {
+        self.node_weights().next().map_or(1, |w| w.as_ref().len())
+    }
+
+    fn vertex_weights(&self, v: Self::Vertex) -> Vec<usize> {
+        self.node_weight(v).unwrap().as_ref().to_vec()
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<N, E: Clone + Into<usize>, Ty: petgraph::EdgeType> GraphLikeEdgeWeighted for petgraph::Graph<N, E, Ty> {
+    fn edge_weight(&self, a: Self::Vertex, b: Self::Vertex) -> usize {
+        let mut weight = self.edges_connecting(a, b).map(|e| e.weight().clone().into()).sum();
+        if self.is_directed() && a != b {
+            weight += self.edges_connecting(b, a).map(|e| e.weight().clone().into()).sum::<usize>();
+        }
+        weight
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<N, E: Clone + Into<usize>, Ty: petgraph::EdgeType> GraphLikeEdgeWeighted for petgraph::stable_graph::StableGraph<N, E, Ty> {
+    fn edge_weight(&self, a: Self::Vertex, b: Self::Vertex) -> usize {
+        let mut weight = self.edges_connecting(a, b).map(|e| e.weight().clone().into()).sum();
+        if self.is_directed() && a != b {
+            weight += self.edges_connecting(b, a).map(|e| e.weight().clone().into()).sum::<usize>();
+        }
+        weight
+    }
+}
+
+/// ZX-diagrams from quizx. As a single weight, each spider counts one plus one more
+/// if its phase is non-Clifford, so weighted separators stay balanced in diagrams
+/// with few T spiders while still preferring to cut Clifford ones. As multiple
+/// constraints, spiders are weighted by both count and T-count.
+#[cfg(feature = "quizx")]
+macro_rules! impl_quizx {
+    ($graph:ty) => {
+        impl GraphLike for $graph {
+            type Vertex = quizx::graph::V;
+
+            fn vertices(&self) -> Vec<Self::Vertex> {
+                quizx::graph::GraphLike::vertices(self).collect()
+            }
+
+            fn has_edge(&self, a: Self::Vertex, b: Self::Vertex) -> bool {
+                quizx::graph::GraphLike::connected(self, a, b)
+            }
+
+            fn neighbors(&self, a: Self::Vertex) -> Vec<Self::Vertex> {
+                quizx::graph::GraphLike::neighbors(self, a).collect()
+            }
+        }
+
+        impl GraphLikeWeighted for $graph {
+            fn vertex_weight(&self, v: Self::Vertex) -> usize {
+                1 + non_clifford(self, v)
+            }
+        }
+
+        impl GraphLikeMultiWeighted for $graph {
+            fn num_constraints(&self) -> usize {
+                2
+            }
+
+            fn vertex_weights(&self, v: Self::Vertex) -> Vec<usize> {
+                vec![1, non_clifford(self, v)]
+            }
+        }
+    };
+}
+
+/// 1 if the phase of spider `v` is not a multiple of pi/2, and 0 otherwise.
+#[cfg(feature = "quizx")]
+fn non_clifford<G: quizx::graph::GraphLike>(graph: &G, v: quizx::graph::V) -> usize {
+    (*graph.phase(v).denom() > 2) as usize
+}
+
+#[cfg(feature = "quizx")]
+impl_quizx!(quizx::vec_graph::Graph);
+
+#[cfg(feature = "quizx")]
+impl_quizx!(quizx::hash_graph::Graph);
+
 pub struct Graph<G: GraphLike> {
     map: Vec<G::Vertex>,
     xadj: Vec<sys::idx_t>,
     adjncy: Vec<sys::idx_t>,
     vwgt: Vec<sys::idx_t>,
+    adjwgt: Option<Vec<sys::idx_t>>,
+    vsize: Option<Vec<sys::idx_t>>,
+    /// The number of weights per vertex, stored consecutively in `vwgt`.
+    ncon: sys::idx_t,
+    #[cfg_attr(feature = "native", allow(dead_code))]
     nvtxs: sys::idx_t
 }
 
@@ -64,22 +208,59 @@ impl<G: GraphLikeWeighted> Graph<G> {
     pub fn new_weighted(graph: &G) -> Self {
         let map: Vec<_> = graph.vertices();
         let nvtxs = map.len() as sys::idx_t;
-        let mut vwgt = Vec::new();
-        let mut xadj = vec![0];
-        let mut adjncy = Vec::new();
-        
+        let vwgt = map.iter()
+            .map(|a| graph.vertex_weight(a.clone()) as sys::idx_t)
+            .collect();
+        let (xadj, adjncy) = adjacency(graph, &map);
+
+        Graph { map, xadj, adjncy, vwgt, adjwgt: None, vsize: None, ncon: 1, nvtxs }
+    }
+}
+
+impl<G: GraphLikeMultiWeighted> Graph<G> {
+    /// Build a graph whose vertices carry `graph.num_constraints()` weights each.
+    /// Separators minimise the first weight while keeping both halves balanced in
+    /// every weight, and partitions balance every weight across the parts.
+    ///
+    /// Fails if there are no constraints or a vertex does not have exactly
+    /// `num_constraints` weights.
+    pub fn new_multi_weighted(graph: &G) -> Result<Self, Error> {
+        let map: Vec<_> = graph.vertices();
+        let nvtxs = map.len() as sys::idx_t;
+        let ncon = graph.num_constraints();
+        if ncon == 0 {
+            return Err(Error::InvalidArgument("a graph needs at least one vertex weight".to_string()));
+        }
+
+        let mut vwgt = Vec::with_capacity(map.len() * ncon);
         for a in &map {
-            for (j, b) in map.iter().enumerate() {
-                if graph.has_edge(a.clone(), b.clone()) || graph.has_edge(b.clone(), a.clone()) {
-                    adjncy.push(j as sys::idx_t);
-                }
+            let weights = graph.vertex_weights(a.clone());
+            if weights.len() != ncon {
+                return Err(Error::InvalidArgument(format!(
+                    "vertex {:?} has {} weights, expected {}", a, weights.len(), ncon
+                )));
             }
-            
-            xadj.push(adjncy.len() as sys::idx_t);
-            vwgt.push(graph.vertex_weight(a.clone()) as sys::idx_t)
+            vwgt.extend(weights.into_iter().map(|w| w as sys::idx_t));
         }
+        let (xadj, adjncy) = adjacency(graph, &map);
 
-        Graph { map, xadj, adjncy, vwgt, nvtxs }
+        Ok(Graph { map, xadj, adjncy, vwgt, adjwgt: None, vsize: None, ncon: ncon as sys::idx_t, nvtxs })
+    }
+}
+
+impl<G: GraphLikeEdgeWeighted> Graph<G> {
+    /// Weight the edges of this graph by `graph.edge_weight`. Edge weights are used
+    /// by `partition`; vertex separators only ever minimise the weight of the
+    /// separator vertices, so they are unaffected.
+    pub fn with_edge_weights(mut self, graph: &G) -> Self {
+        let mut adjwgt = Vec::with_capacity(self.adjncy.len());
+        for (i, a) in self.map.iter().enumerate() {
+            for &j in &self.adjncy[self.xadj[i] as usize..self.xadj[i + 1] as usize] {
+                adjwgt.push(graph.edge_weight(a.clone(), self.map[j as usize].clone()) as sys::idx_t);
+            }
+        }
+        self.adjwgt = Some(adjwgt);
+        self
     }
 }
 
@@ -88,26 +269,158 @@ impl<G: GraphLike> Graph<G> {
         let map: Vec<_> = graph.vertices();
         let nvtxs = map.len() as sys::idx_t;
         let vwgt = vec![1; nvtxs as usize];
-        let mut xadj = vec![0];
-        let mut adjncy = Vec::new();
-        
-        for a in &map {
-            for (j, b) in map.iter().enumerate() {
-                if graph.has_edge(a.clone(), b.clone()) || graph.has_edge(b.clone(), a.clone()) {
-                    adjncy.push(j as sys::idx_t);
+        let (xadj, adjncy) = adjacency(graph, &map);
+
+        Graph { map, xadj, adjncy, vwgt, adjwgt: None, vsize: None, ncon: 1, nvtxs }
+    }
+
+    /// Set the communication size of each vertex, which `partition` uses in place
+    /// of edge weights when minimising total communication volume.
+    pub fn with_vertex_sizes(mut self, size: impl Fn(G::Vertex) -> usize) -> Self {
+        self.vsize = Some(self.map.iter().map(|v| size(v.clone()) as sys::idx_t).collect());
+        self
+    }
+
+    /// Split the graph into two halves with no edges between them by removing a
+    /// small set of `cut` vertices. With several vertex weights, the METIS backend
+    /// bisects the graph balancing every weight and then takes a minimum vertex
+    /// cover of the cut edges, since METIS' own separator routines accept only one.
+    /// That bisection ignores refinement, initial partitioning and objective
+    /// settings that only apply to separators or k-way partitioning.
+    pub fn vertex_separator(&self, options: &Options) -> Result<VertexSeparator<G>, Error> {
+        self.check_imbalances(options)?;
+        let part = self.separator_parts(options)?;
+        Ok(self.separator(&part))
+    }
+
+    /// Improve an existing separator of this graph in place, for instance one
+    /// computed before a small change to the graph. Edges that now join `left` to
+    /// `right` are first repaired by moving their `right` endpoint into `cut`.
+    /// METIS refines with respect to the first vertex weight only.
+    pub fn refine_separator(&self, sep: &mut VertexSeparator<G>, options: &Options) -> Result<(), Error> {
+        self.check_imbalances(options)?;
+        let index = self.map.iter().cloned()
+            .enumerate()
+            .map(|(i, v)| (v, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut part = vec![sys::idx_t::MAX; self.map.len()];
+        for (p, set) in [&sep.left, &sep.right, &sep.cut].into_iter().enumerate() {
+            for v in set {
+                match index.get(v) {
+                    Some(&i) => part[i] = p as sys::idx_t,
+                    None => return Err(Error::InvalidArgument(format!("vertex {:?} is not in the graph", v)))
+                }
+            }
+        }
+
+        if let Some(i) = part.iter().position(|&p| p == sys::idx_t::MAX) {
+            return Err(Error::InvalidArgument(format!("vertex {:?} is missing from the separator", self.map[i])));
+        }
+
+        for i in 0..self.map.len() {
+            if part[i] == 0 {
+                for &j in &self.adjncy[self.xadj[i] as usize..self.xadj[i + 1] as usize] {
+                    if part[j as usize] == 1 {
+                        part[j as usize] = 2;
+                    }
                 }
             }
-            
-            xadj.push(adjncy.len() as sys::idx_t);
-        } 
+        }
 
-        Graph { map, xadj, adjncy, vwgt, nvtxs }
+        self.refine_parts(&mut part, options)?;
+        *sep = self.separator(&part);
+        Ok(())
     }
 
-    pub fn vertex_separator(&self, options: &Options) -> Result<VertexSeparator<G>, Error> {
+    fn check_imbalances(&self, options: &Options) -> Result<(), Error> {
+        match &options.ubvec {
+            Some(ubvec) if ubvec.len() != self.ncon as usize => Err(Error::InvalidArgument(format!(
+                "expected {} imbalance tolerances, one per vertex weight, got {}", self.ncon, ubvec.len()
+            ))),
+            _ => Ok(())
+        }
+    }
+
+    /// The first weight of each vertex, for METIS routines that take only one.
+    #[cfg(not(feature = "native"))]
+    fn primary_weights(&self) -> std::borrow::Cow<'_, [sys::idx_t]> {
+        match self.ncon {
+            1 => std::borrow::Cow::Borrowed(&self.vwgt),
+            ncon => std::borrow::Cow::Owned(self.vwgt.iter().step_by(ncon as usize).copied().collect())
+        }
+    }
+
+    fn separator(&self, part: &[sys::idx_t]) -> VertexSeparator<G> {
+        let mut partition = VertexSeparator {
+            left: Vec::new(), right: Vec::new(), cut: Vec::new()
+        };
+
+        for (v, p) in self.map.iter().zip(part) {
+            match p {
+                0 => partition.left.push(v.clone()),
+                1 => partition.right.push(v.clone()),
+                2 => partition.cut.push(v.clone()),
+                _ => ()
+            }
+        }
+
+        partition
+    }
+
+    #[cfg(feature = "native")]
+    fn refine_parts(&self, part: &mut [sys::idx_t], options: &Options) -> Result<(), Error> {
+        native::refine_separator(&self.xadj, &self.adjncy, &self.vwgt, self.ncon as usize, part, options);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn refine_parts(&self, part: &mut [sys::idx_t], options: &Options) -> Result<(), Error> {
+        let ufactor = match options.options[sys::moptions_et_METIS_OPTION_UFACTOR as usize] {
+            u if u < 0 => 200,
+            u => u
+        };
+        // Every vertex may move to either side.
+        let mut hmarker = vec![-1; self.map.len()];
+        let vwgt = self.primary_weights();
+        let error = with_metis(|| unsafe {
+            sys::METIS_NodeRefine(
+                self.nvtxs,
+                self.xadj.as_ptr() as *mut _,
+                vwgt.as_ptr() as *mut _,
+                self.adjncy.as_ptr() as *mut _,
+                part.as_mut_ptr(),
+                hmarker.as_mut_ptr(),
+                1.0 + 0.001 * ufactor as sys::real_t
+            )
+        });
+
+        check(error, "METIS_NodeRefine")
+    }
+
+    #[cfg(feature = "native")]
+    fn separator_parts(&self, options: &Options) -> Result<Vec<sys::idx_t>, Error> {
+        Ok(native::vertex_separator(&self.xadj, &self.adjncy, &self.vwgt, self.ncon as usize, options))
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn separator_parts(&self, options: &Options) -> Result<Vec<sys::idx_t>, Error> {
+        // Like the native backend, separate an empty graph into three empty sets
+        // rather than letting METIS divide by zero.
+        if self.map.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.ncon > 1 {
+            let options = options.for_bisection();
+            let (mut part, _) = self.partition_parts(sys::METIS_PartGraphRecursive, "METIS_PartGraphRecursive", 2, &options)?;
+            self.cover_cut(&mut part);
+            return Ok(part);
+        }
+
         let mut sepsize = 0;
         let mut part = vec![0; self.nvtxs as usize];
-        let error = unsafe {
+        let error = with_metis(|| unsafe {
              sys::METIS_ComputeVertexSeparator(
                 &self.nvtxs as *const _ as *mut _,
                 self.xadj.as_ptr() as *mut _,
@@ -117,30 +430,342 @@ impl<G: GraphLike> Graph<G> {
                 &mut sepsize as *mut _,
                 part.as_mut_ptr()
             )
-        };
+        });
+
+        check(error, "METIS_ComputeVertexSeparator")?;
+
+        Ok(part)
+    }
+}
+
+/// Operations that have no pure-Rust implementation and need METIS to be linked.
+#[cfg(not(feature = "native"))]
+impl<G: GraphLike> Graph<G> {
+    /// Split the graph into `nparts` parts using multilevel k-way partitioning.
+    pub fn partition(&self, nparts: usize, options: &Options) -> Result<Partition<G>, Error> {
+        self.partition_with(sys::METIS_PartGraphKway, "METIS_PartGraphKway", nparts, options)
+    }
+
+    /// Split the graph into `nparts` parts using multilevel recursive bisection,
+    /// which METIS recommends over k-way when `nparts` is small.
+    pub fn partition_recursive(&self, nparts: usize, options: &Options) -> Result<Partition<G>, Error> {
+        self.partition_with(sys::METIS_PartGraphRecursive, "METIS_PartGraphRecursive", nparts, options)
+    }
 
-        if error == sys::rstatus_et_METIS_OK {
-            let mut partition = VertexSeparator {
-                left: Vec::new(), right: Vec::new(), cut: Vec::new()
-            };
-
-            for i in 0..self.nvtxs as usize {
-                match part[i] {
-                    0 => partition.left.push(self.map[i].clone()),
-                    1 => partition.right.push(self.map[i].clone()),
-                    2 => partition.cut.push(self.map[i].clone()),
-                    _ => ()
+    fn partition_with(&self, method: PartitionFn, name: &'static str, nparts: usize, options: &Options) -> Result<Partition<G>, Error> {
+        self.check_imbalances(options)?;
+        let (part, objval) = self.partition_parts(method, name, nparts, options)?;
+
+        let assignment = self.map.iter().cloned()
+            .zip(part.into_iter().map(|p| p as usize))
+            .collect();
+
+        Ok(Partition { assignment, nparts, objective: objval as usize })
+    }
+
+    fn partition_parts(&self, method: PartitionFn, name: &'static str, nparts: usize, options: &Options) -> Result<(Vec<sys::idx_t>, sys::idx_t), Error> {
+        // METIS divides by the number of vertices and by log2(nparts), so an empty
+        // graph or a single part would crash it.
+        if self.map.is_empty() || nparts == 1 {
+            return Ok((vec![0; self.map.len()], 0));
+        }
+
+        let mut nparts = nparts as sys::idx_t;
+        let mut objval = 0;
+        let mut part = vec![0; self.nvtxs as usize];
+        let ubvec = options.ubvec.as_ref().map_or(std::ptr::null_mut(), |u| u.as_ptr() as *mut _);
+        let error = with_metis(|| unsafe {
+            method(
+                &self.nvtxs as *const _ as *mut _,
+                &self.ncon as *const _ as *mut _,
+                self.xadj.as_ptr() as *mut _,
+                self.adjncy.as_ptr() as *mut _,
+                self.vwgt.as_ptr() as *mut _,
+                optional_ptr(&self.vsize),
+                optional_ptr(&self.adjwgt),
+                &mut nparts as *mut _,
+                std::ptr::null_mut(),
+                ubvec,
+                options.options.as_ptr() as *mut _,
+                &mut objval as *mut _,
+                part.as_mut_ptr()
+            )
+        });
+
+        check(error, name)?;
+
+        Ok((part, objval))
+    }
+
+    /// Turn a bisection into a vertex separator by moving a minimum vertex cover of
+    /// the cut edges into the separator. The cut edges form a bipartite graph, so
+    /// the cover is found from a maximum matching by König's theorem.
+    fn cover_cut(&self, part: &mut [sys::idx_t]) {
+        // `visited` is marked with the root of each search, so it never needs clearing.
+        fn augment(i: usize, root: usize, cross: &[Vec<usize>], mate: &mut [usize], visited: &mut [usize]) -> bool {
+            for &j in &cross[i] {
+                if visited[j] != root {
+                    visited[j] = root;
+                    if mate[j] == usize::MAX || augment(mate[j], root, cross, mate, visited) {
+                        mate[i] = j;
+                        mate[j] = i;
+                        return true;
+                    }
                 }
             }
+            false
+        }
+
+        let n = self.map.len();
+        let cross = (0..n)
+            .map(|i| match part[i] {
+                0 => self.adjncy[self.xadj[i] as usize..self.xadj[i + 1] as usize].iter()
+                    .map(|&j| j as usize)
+                    .filter(|&j| part[j] == 1)
+                    .collect(),
+                _ => Vec::new()
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        let mut mate = vec![usize::MAX; n];
+        let mut visited = vec![usize::MAX; n];
+        for i in 0..n {
+            if !cross[i].is_empty() {
+                augment(i, i, &cross, &mut mate, &mut visited);
+            }
+        }
+
+        // Vertices reachable from unmatched left vertices by alternating paths.
+        let mut reached = vec![false; n];
+        let mut stack = (0..n).filter(|&i| !cross[i].is_empty() && mate[i] == usize::MAX).collect::<Vec<_>>();
+        for &i in &stack {
+            reached[i] = true;
+        }
+        while let Some(i) = stack.pop() {
+            for &j in &cross[i] {
+                if !reached[j] {
+                    reached[j] = true;
+                    if mate[j] != usize::MAX && !reached[mate[j]] {
+                        reached[mate[j]] = true;
+                        stack.push(mate[j]);
+                    }
+                }
+            }
+        }
+
+        for i in 0..n {
+            let left = !cross[i].is_empty() && !reached[i];
+            let right = part[i] == 1 && reached[i];
+            if left || right {
+                part[i] = 2;
+            }
+        }
+    }
+
+    /// Compute a fill-reducing ordering by recursive nested dissection. Only the
+    /// first vertex weight is used.
+    pub fn nested_dissection(&self, options: &Options) -> Result<Ordering<G>, Error> {
+        if self.map.is_empty() {
+            return Ok(self.ordering(&[], &[]));
+        }
+
+        let mut perm = vec![0; self.nvtxs as usize];
+        let mut iperm = vec![0; self.nvtxs as usize];
+        let vwgt = self.primary_weights();
+        let error = with_metis(|| unsafe {
+            sys::METIS_NodeND(
+                &self.nvtxs as *const _ as *mut _,
+                self.xadj.as_ptr() as *mut _,
+                self.adjncy.as_ptr() as *mut _,
+                vwgt.as_ptr() as *mut _,
+                options.options.as_ptr() as *mut _,
+                perm.as_mut_ptr(),
+                iperm.as_mut_ptr()
+            )
+        });
+
+        check(error, "METIS_NodeND")?;
+
+        Ok(self.ordering(&perm, &iperm))
+    }
+
+    /// Compute a nested dissection ordering that stops after splitting the graph into
+    /// `npes` parts, which must be a power of two, and report the size of each part
+    /// and separator in the resulting tree.
+    pub fn nested_dissection_tree(&self, npes: usize, options: &Options) -> Result<(Ordering<G>, SeparatorTree), Error> {
+        if !npes.is_power_of_two() {
+            return Err(Error::InvalidArgument(format!("npes must be a power of two, got {}", npes)));
+        }
 
-            Ok(partition)
-        } else {
-            match error {
-                sys::rstatus_et_METIS_ERROR_MEMORY => Err(Error::Memory),
-                sys::rstatus_et_METIS_ERROR_INPUT => Err(Error::Input),
-                _ => Err(Error::Other)
+        if self.map.is_empty() {
+            let tree = SeparatorTree { parts: vec![0; npes], separators: vec![0; npes - 1] };
+            return Ok((self.ordering(&[], &[]), tree));
+        }
+
+        // A single leaf holds every vertex, ordered as a full nested dissection
+        // would; METIS_NodeNDP does not fill in the sizes in this case.
+        if npes == 1 {
+            let tree = SeparatorTree { parts: vec![self.map.len()], separators: Vec::new() };
+            return Ok((self.nested_dissection(options)?, tree));
+        }
+
+        let mut perm = vec![0; self.nvtxs as usize];
+        let mut iperm = vec![0; self.nvtxs as usize];
+        let mut sizes = vec![0; 2 * npes - 1];
+        let vwgt = self.primary_weights();
+        let error = with_metis(|| unsafe {
+            sys::METIS_NodeNDP(
+                self.nvtxs,
+                self.xadj.as_ptr() as *mut _,
+                self.adjncy.as_ptr() as *mut _,
+                vwgt.as_ptr() as *mut _,
+                npes as sys::idx_t,
+                options.options.as_ptr() as *mut _,
+                perm.as_mut_ptr(),
+                iperm.as_mut_ptr(),
+                sizes.as_mut_ptr()
+            )
+        });
+
+        check(error, "METIS_NodeNDP")?;
+
+        let mut sizes = sizes.into_iter().map(|s| s as usize);
+        let tree = SeparatorTree {
+            parts: sizes.by_ref().take(npes).collect(),
+            separators: sizes.collect()
+        };
+
+        Ok((self.ordering(&perm, &iperm), tree))
+    }
+
+    /// Order the vertices so that neighbours tend to be close together, which makes
+    /// repeated sweeps over the adjacency structure more cache friendly.
+    pub fn cache_friendly_order(&self) -> Result<Ordering<G>, Error> {
+        if self.map.is_empty() {
+            return Ok(self.ordering(&[], &[]));
+        }
+
+        let mut part = vec![0; self.map.len()];
+        let mut old2new = vec![0; self.map.len()];
+        let error = with_metis(|| unsafe {
+            sys::METIS_CacheFriendlyReordering(
+                self.nvtxs,
+                self.xadj.as_ptr() as *mut _,
+                self.adjncy.as_ptr() as *mut _,
+                part.as_mut_ptr(),
+                old2new.as_mut_ptr()
+            )
+        });
+
+        check(error, "METIS_CacheFriendlyReordering")?;
+
+        let mut new2old = vec![0; self.map.len()];
+        for (i, &j) in old2new.iter().enumerate() {
+            new2old[j as usize] = i as sys::idx_t;
+        }
+
+        Ok(self.ordering(&new2old, &old2new))
+    }
+
+    fn ordering(&self, perm: &[sys::idx_t], iperm: &[sys::idx_t]) -> Ordering<G> {
+        Ordering {
+            order: perm.iter().map(|&i| self.map[i as usize].clone()).collect(),
+            position: self.map.iter().cloned()
+                .zip(iperm.iter().map(|&i| i as usize))
+                .collect()
+        }
+    }
+}
+
+/// The operations that only METIS provides still exist under the `native` feature,
+/// so that enabling it never breaks other users of this crate, but they always fail.
+#[cfg(feature = "native")]
+impl<G: GraphLike> Graph<G> {
+    pub fn partition(&self, _nparts: usize, _options: &Options) -> Result<Partition<G>, Error> {
+        needs_metis("METIS_PartGraphKway")
+    }
+
+    pub fn partition_recursive(&self, _nparts: usize, _options: &Options) -> Result<Partition<G>, Error> {
+        needs_metis("METIS_PartGraphRecursive")
+    }
+
+    pub fn nested_dissection(&self, _options: &Options) -> Result<Ordering<G>, Error> {
+        needs_metis("METIS_NodeND")
+    }
+
+    pub fn nested_dissection_tree(&self, _npes: usize, _options: &Options) -> Result<(Ordering<G>, SeparatorTree), Error> {
+        needs_metis("METIS_NodeNDP")
+    }
+
+    pub fn cache_friendly_order(&self) -> Result<Ordering<G>, Error> {
+        needs_metis("METIS_CacheFriendlyReordering")
+    }
+}
+
+#[cfg(feature = "native")]
+fn needs_metis<T>(function: &'static str) -> Result<T, Error> {
+    Err(Error::Unsupported { function })
+}
+
+/// Build the CSR arrays for `graph` with vertices numbered as in `map`, dropping
+/// self-loops and parallel edges which METIS does not accept.
+fn adjacency<G: GraphLike>(graph: &G, map: &[G::Vertex]) -> (Vec<sys::idx_t>, Vec<sys::idx_t>) {
+    let index = map.iter().cloned()
+        .enumerate()
+        .map(|(i, v)| (v, i))
+        .collect::<HashMap<_, _>>();
+    let mut seen = vec![usize::MAX; map.len()];
+    let mut xadj = vec![0];
+    let mut adjncy = Vec::new();
+
+    for (i, a) in map.iter().enumerate() {
+        for b in graph.neighbors(a.clone()) {
+            if let Some(&j) = index.get(&b) {
+                if j != i && seen[j] != i {
+                    seen[j] = i;
+                    adjncy.push(j as sys::idx_t);
+                }
             }
         }
+
+        xadj.push(adjncy.len() as sys::idx_t);
+    }
+
+    (xadj, adjncy)
+}
+
+#[cfg(not(feature = "native"))]
+fn optional_ptr(array: &Option<Vec<sys::idx_t>>) -> *mut sys::idx_t {
+    array.as_ref().map_or(std::ptr::null_mut(), |a| a.as_ptr() as *mut _)
+}
+
+#[cfg(not(feature = "native"))]
+type PartitionFn = unsafe extern "C" fn(
+    *mut sys::idx_t, *mut sys::idx_t, *mut sys::idx_t, *mut sys::idx_t, *mut sys::idx_t,
+    *mut sys::idx_t, *mut sys::idx_t, *mut sys::idx_t, *mut sys::real_t, *mut sys::real_t,
+    *mut sys::idx_t, *mut sys::idx_t, *mut sys::idx_t
+) -> std::os::raw::c_int;
+
+/// Serialises calls into METIS, which keeps its random number state in globals
+/// that every call reseeds, so concurrent calls would race and make seeded
+/// results depend on timing.
+#[cfg(not(feature = "native"))]
+static METIS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Run `f`, which calls into METIS, while no other thread does. The lock guards
+/// nothing on the Rust side, so it stays usable after a panic.
+#[cfg(not(feature = "native"))]
+fn with_metis<T>(f: impl FnOnce() -> T) -> T {
+    let _metis = METIS.lock().unwrap_or_else(|e| e.into_inner());
+    f()
+}
+
+#[cfg(not(feature = "native"))]
+fn check(error: std::os::raw::c_int, function: &'static str) -> Result<(), Error> {
+    if error == sys::rstatus_et_METIS_OK {
+        Ok(())
+    } else {
+        Err(Error::Metis { code: error, function })
     }
 }
 
@@ -151,30 +776,407 @@ pub struct VertexSeparator<G: GraphLike> {
     pub cut: Vec<G::Vertex>
 }
 
+impl<G: GraphLike> VertexSeparator<G> {
+    /// Check that `left`, `right` and `cut` partition the vertices of `graph` and
+    /// that no edge joins `left` to `right` directly.
+    pub fn validate(&self, graph: &G) -> Result<(), Error> {
+        let sets = [("left", &self.left), ("right", &self.right), ("cut", &self.cut)];
+        let mut side = HashMap::new();
+        for (name, set) in sets {
+            for v in set {
+                if let Some(other) = side.insert(v.clone(), name) {
+                    return Err(Error::InvalidSeparator(format!("vertex {:?} is in both {} and {}", v, other, name)));
+                }
+            }
+        }
+
+        let vertices = graph.vertices();
+        for v in &vertices {
+            if !side.contains_key(v) {
+                return Err(Error::InvalidSeparator(format!("vertex {:?} is in none of left, right and cut", v)));
+            }
+        }
+
+        if side.len() != vertices.len() {
+            let stray = side.keys().find(|v| !vertices.contains(v)).unwrap();
+            return Err(Error::InvalidSeparator(format!("vertex {:?} is not in the graph", stray)));
+        }
+
+        for a in &self.left {
+            for b in graph.neighbors(a.clone()) {
+                if side.get(&b) == Some(&"right") {
+                    return Err(Error::InvalidSeparator(format!("edge {:?}-{:?} joins left and right", a, b)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Partition<G: GraphLike> {
+    /// The part each vertex was assigned to, in `0..nparts`.
+    pub assignment: Vec<(G::Vertex, usize)>,
+    pub nparts: usize,
+    /// The total weight of the edges whose endpoints lie in different parts, or
+    /// the communication volume, depending on the objective chosen in `Options`.
+    pub objective: usize
+}
+
+impl<G: GraphLike> Partition<G> {
+    pub fn parts(&self) -> Vec<Vec<G::Vertex>> {
+        let mut parts = vec![Vec::new(); self.nparts];
+        for (v, p) in &self.assignment {
+            parts[*p].push(v.clone());
+        }
+        parts
+    }
+}
+
+/// A vertex ordering, such as the elimination order produced by nested dissection.
+#[derive(Debug,Clone)]
+pub struct Ordering<G: GraphLike> {
+    /// The vertices in their new order (METIS' `perm`).
+    pub order: Vec<G::Vertex>,
+    /// The new position of each vertex (METIS' `iperm`).
+    pub position: Vec<(G::Vertex, usize)>
+}
+
+#[cfg(feature = "petgraph")]
+impl<N: Clone, E: Clone, Ty: petgraph::EdgeType> Ordering<petgraph::stable_graph::StableGraph<N, E, Ty>> {
+    /// Copy `graph` with its nodes inserted in this order, so that the node at
+    /// `order[i]` becomes `NodeIndex::new(i)`. Edges keep their relative order.
+    pub fn relabel(&self, graph: &petgraph::stable_graph::StableGraph<N, E, Ty>) -> petgraph::stable_graph::StableGraph<N, E, Ty> {
+        let mut relabelled = petgraph::stable_graph::StableGraph::with_capacity(graph.node_count(), graph.edge_count());
+        let new = self.order.iter()
+            .map(|&v| (v, relabelled.add_node(graph[v].clone())))
+            .collect::<HashMap<_, _>>();
+
+        for e in graph.edge_indices() {
+            let (a, b) = graph.edge_endpoints(e).unwrap();
+            relabelled.add_edge(new[&a], new[&b], graph[e].clone());
+        }
+
+        relabelled
+    }
+}
+
+/// Sizes of the pieces of a nested dissection stopped after `npes` leaves.
+#[derive(Debug,Clone)]
+pub struct SeparatorTree {
+    /// The number of vertices in each of the `npes` leaf parts.
+    pub parts: Vec<usize>,
+    /// The number of vertices in each of the `npes - 1` separators, starting
+    /// from the deepest level and ending with the top-level separator.
+    pub separators: Vec<usize>
+}
+
+#[derive(Debug,Clone)]
 pub struct Options {
-    options: [sys::idx_t; sys::METIS_NOPTIONS as usize]
+    options: [sys::idx_t; sys::METIS_NOPTIONS as usize],
+    ubvec: Option<Vec<sys::real_t>>
 }
 
 impl Default for Options {
+    #[cfg(not(feature = "native"))]
     fn default() -> Self {
         let mut options = [0; sys::METIS_NOPTIONS as usize];
-        unsafe {
+        with_metis(|| unsafe {
             sys::METIS_SetDefaultOptions(options.as_mut_ptr());
-        }
-        Options { options }
+        });
+        Options { options, ubvec: None }
+    }
+
+    // METIS_SetDefaultOptions marks every option as unset with -1.
+    #[cfg(feature = "native")]
+    fn default() -> Self {
+        Options { options: [-1; sys::METIS_NOPTIONS as usize], ubvec: None }
     }
 }
 
 impl Options {
-    pub fn max_imbalance(mut self, factor: usize) -> Self {
-        self.options[sys::moptions_et_METIS_OPTION_UFACTOR as usize] = factor as sys::idx_t;
+    fn set(mut self, option: sys::moptions_et, value: sys::idx_t) -> Self {
+        self.options[option as usize] = value;
+        self
+    }
+
+    /// These options adapted for the recursive bisection behind multi-weight
+    /// separators: initial partitioning schemes and objectives METIS would reject
+    /// there are unset, and the imbalance defaults to 200 as for single-weight
+    /// separators and the native backend, instead of METIS' much stricter
+    /// multi-constraint default of 10. METIS always refines bisections with FM.
+    #[cfg(not(feature = "native"))]
+    fn for_bisection(&self) -> Options {
+        let mut options = self.clone();
+        let mut keep_only = |option: sys::moptions_et, allowed: &[u32]| {
+            let value = &mut options.options[option as usize];
+            if !allowed.contains(&(*value as u32)) {
+                *value = -1;
+            }
+        };
+        keep_only(sys::moptions_et_METIS_OPTION_IPTYPE, &[sys::miptype_et_METIS_IPTYPE_GROW, sys::miptype_et_METIS_IPTYPE_RANDOM]);
+        keep_only(sys::moptions_et_METIS_OPTION_OBJTYPE, &[sys::mobjtype_et_METIS_OBJTYPE_CUT]);
+
+        let ufactor = &mut options.options[sys::moptions_et_METIS_OPTION_UFACTOR as usize];
+        if *ufactor < 0 {
+            *ufactor = 200;
+        }
+        options
+    }
+
+    /// Maximum allowed load imbalance, in thousandths above a perfect balance.
+    pub fn max_imbalance(self, factor: usize) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_UFACTOR, factor as sys::idx_t)
+    }
+
+    /// Maximum allowed load imbalance for each vertex weight, as a ratio such as
+    /// 1.05, overriding `max_imbalance` for graphs built with `new_multi_weighted`.
+    pub fn max_imbalances(mut self, ubvec: Vec<f32>) -> Self {
+        self.ubvec = Some(ubvec.into_iter().map(|u| u as sys::real_t).collect());
         self
     }
+
+    /// Seed for METIS' random number generator, making runs reproducible.
+    pub fn seed(self, seed: i32) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_SEED, seed)
+    }
+
+    /// Number of different partitionings to compute, keeping the best.
+    pub fn cuts(self, ncuts: usize) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_NCUTS, ncuts as sys::idx_t)
+    }
+
+    /// Number of different separators to compute at each level of nested
+    /// dissection or vertex separation, keeping the best.
+    pub fn separators(self, nseps: usize) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_NSEPS, nseps as sys::idx_t)
+    }
+
+    /// Number of refinement iterations at each level of uncoarsening.
+    pub fn refinement_iterations(self, niter: usize) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_NITER, niter as sys::idx_t)
+    }
+
+    /// Whether to merge vertices with identical adjacency lists before ordering.
+    pub fn compress(self, compress: bool) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_COMPRESS, compress as sys::idx_t)
+    }
+
+    /// Whether k-way partitioning must produce connected parts.
+    pub fn contiguous(self, contig: bool) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_CONTIG, contig as sys::idx_t)
+    }
+
+    pub fn coarsening(self, ctype: Coarsening) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_CTYPE, ctype as sys::idx_t)
+    }
+
+    pub fn refinement(self, rtype: Refinement) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_RTYPE, rtype as sys::idx_t)
+    }
+
+    pub fn initial_partitioning(self, iptype: InitialPartitioning) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_IPTYPE, iptype as sys::idx_t)
+    }
+
+    pub fn objective(self, objtype: Objective) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_OBJTYPE, objtype as sys::idx_t)
+    }
+
+    pub fn debug(self, level: DebugLevel) -> Self {
+        self.set(sys::moptions_et_METIS_OPTION_DBGLVL, level.0 as sys::idx_t)
+    }
+}
+
+/// How vertices are matched when coarsening the graph.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Coarsening {
+    RandomMatching = sys::mctype_et_METIS_CTYPE_RM as isize,
+    SortedHeavyEdgeMatching = sys::mctype_et_METIS_CTYPE_SHEM as isize
+}
+
+/// How partitions are refined while uncoarsening.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Refinement {
+    FiducciaMattheyses = sys::mrtype_et_METIS_RTYPE_FM as isize,
+    Greedy = sys::mrtype_et_METIS_RTYPE_GREEDY as isize,
+    TwoSidedNode = sys::mrtype_et_METIS_RTYPE_SEP2SIDED as isize,
+    OneSidedNode = sys::mrtype_et_METIS_RTYPE_SEP1SIDED as isize
+}
+
+/// How the coarsest graph is initially partitioned.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum InitialPartitioning {
+    Grow = sys::miptype_et_METIS_IPTYPE_GROW as isize,
+    Random = sys::miptype_et_METIS_IPTYPE_RANDOM as isize,
+    Edge = sys::miptype_et_METIS_IPTYPE_EDGE as isize,
+    Node = sys::miptype_et_METIS_IPTYPE_NODE as isize,
+    RecursiveBisection = sys::miptype_et_METIS_IPTYPE_METISRB as isize
+}
+
+/// What k-way partitioning minimises.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Objective {
+    EdgeCut = sys::mobjtype_et_METIS_OBJTYPE_CUT as isize,
+    Volume = sys::mobjtype_et_METIS_OBJTYPE_VOL as isize
+}
+
+/// Which diagnostics METIS prints, combined with `|`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub struct DebugLevel(sys::mdbglvl_et);
+
+impl DebugLevel {
+    pub const NONE: DebugLevel = DebugLevel(0);
+    pub const INFO: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_INFO);
+    pub const TIME: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_TIME);
+    pub const COARSEN: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_COARSEN);
+    pub const REFINE: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_REFINE);
+    pub const IPART: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_IPART);
+    pub const MOVEINFO: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_MOVEINFO);
+    pub const SEPINFO: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_SEPINFO);
+    pub const CONNINFO: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_CONNINFO);
+    pub const CONTIGINFO: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_CONTIGINFO);
+    pub const MEMORY: DebugLevel = DebugLevel(sys::mdbglvl_et_METIS_DBG_MEMORY);
+}
+
+impl std::ops::BitOr for DebugLevel {
+    type Output = DebugLevel;
+
+    fn bitor(self, other: DebugLevel) -> DebugLevel {
+        DebugLevel(self.0 | other.0)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug,Clone,PartialEq,Eq)]
 pub enum Error {
-    Input,
-    Memory,
-    Other
+    /// A METIS routine returned the status `code`.
+    Metis { code: sys::rstatus_et, function: &'static str },
+    /// An argument was rejected before reaching METIS.
+    InvalidArgument(String),
+    /// A separator failed `VertexSeparator::validate`.
+    InvalidSeparator(String),
+    /// The METIS routine `function` is needed but was not linked, because the
+    /// `native` feature is enabled.
+    Unsupported { function: &'static str }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Metis { code, function } => {
+                let reason = match *code {
+                    sys::rstatus_et_METIS_ERROR_INPUT => "erroneous inputs and/or options",
+                    sys::rstatus_et_METIS_ERROR_MEMORY => "insufficient memory",
+                    _ => "an unspecified error"
+                };
+                write!(f, "{} failed with status {}: {}", function, code, reason)
+            }
+            Error::InvalidArgument(msg) => write!(f, "invalid argument: {}", msg),
+            Error::InvalidSeparator(msg) => write!(f, "invalid vertex separator: {}", msg),
+            Error::Unsupported { function } => {
+                write!(f, "{} is unavailable with the native feature, which does not link METIS", function)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(not(feature = "native"))]
+#[test]
+fn empty_graph_test() {
+    let graph = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let mgraph = Graph::new(&graph);
+    let options = Options::default();
+
+    let sep = mgraph.vertex_separator(&options).unwrap();
+    assert!(sep.left.is_empty() && sep.right.is_empty() && sep.cut.is_empty());
+
+    let part = mgraph.partition(2, &options).unwrap();
+    assert!(part.assignment.is_empty() && part.objective == 0);
+    let part = mgraph.partition_recursive(2, &options).unwrap();
+    assert!(part.assignment.is_empty() && part.objective == 0);
+
+    let order = mgraph.nested_dissection(&options).unwrap();
+    assert!(order.order.is_empty() && order.position.is_empty());
+    let (order, tree) = mgraph.nested_dissection_tree(4, &options).unwrap();
+    assert!(order.order.is_empty());
+    assert_eq!((tree.parts, tree.separators), (vec![0; 4], vec![0; 3]));
+
+    let order = mgraph.cache_friendly_order().unwrap();
+    assert!(order.order.is_empty() && order.position.is_empty());
+}
+
+#[cfg(not(feature = "native"))]
+#[test]
+fn single_part_test() {
+    let mut graph = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let v = (0..10).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    for i in 0..9 {
+        graph.add_edge(v[i], v[i + 1], ());
+    }
+    let mgraph = Graph::new(&graph);
+    let options = Options::default();
+
+    for part in [mgraph.partition(1, &options), mgraph.partition_recursive(1, &options)] {
+        let part = part.unwrap();
+        assert_eq!(part.parts(), vec![v.clone()]);
+        assert_eq!(part.objective, 0);
+    }
+
+    let (order, tree) = mgraph.nested_dissection_tree(1, &options).unwrap();
+    assert_eq!(order.order.len(), 10);
+    assert_eq!((tree.parts, tree.separators), (vec![10], Vec::new()));
+}
+
+#[test]
+fn validate_test() {
+    use petgraph::graph::NodeIndex;
+
+    let mut graph = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let v = (0..3).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], ());
+    graph.add_edge(v[1], v[2], ());
+    let sep = |left: &[NodeIndex], right: &[NodeIndex], cut: &[NodeIndex]| VertexSeparator::<petgraph::graph::UnGraph<(), ()>> {
+        left: left.to_vec(), right: right.to_vec(), cut: cut.to_vec()
+    };
+    let invalid = |msg: &str| Err(Error::InvalidSeparator(msg.to_string()));
+
+    assert_eq!(sep(&[v[0]], &[v[2]], &[v[1]]).validate(&graph), Ok(()));
+    assert_eq!(
+        sep(&[v[0]], &[v[2]], &[v[1], v[0]]).validate(&graph),
+        invalid("vertex NodeIndex(0) is in both left and cut")
+    );
+    assert_eq!(
+        sep(&[v[0]], &[v[2]], &[]).validate(&graph),
+        invalid("vertex NodeIndex(1) is in none of left, right and cut")
+    );
+    assert_eq!(
+        sep(&[v[0]], &[v[2], NodeIndex::new(7)], &[v[1]]).validate(&graph),
+        invalid("vertex NodeIndex(7) is not in the graph")
+    );
+    assert_eq!(
+        sep(&[v[0]], &[v[1]], &[v[2]]).validate(&graph),
+        invalid("edge NodeIndex(0)-NodeIndex(1) joins left and right")
+    );
+}
+
+#[cfg(not(feature = "native"))]
+#[test]
+fn multi_weighted_separator_test() {
+    let mut graph = petgraph::graph::UnGraph::<[usize; 2], ()>::new_undirected();
+    let v = (0..100).map(|i| graph.add_node([1, i % 3 / 2])).collect::<Vec<_>>();
+    for i in 0..100 {
+        graph.add_edge(v[i], v[(i + 1) % 100], ());
+        graph.add_edge(v[i], v[(i + 7) % 100], ());
+    }
+
+    // Separator settings that METIS' bisection would reject are dropped.
+    let options = Options::default()
+        .refinement(Refinement::TwoSidedNode)
+        .initial_partitioning(InitialPartitioning::Node);
+    let sep = Graph::new_multi_weighted(&graph).unwrap().vertex_separator(&options).unwrap();
+    sep.validate(&graph).unwrap();
+    assert!(!sep.left.is_empty() && !sep.right.is_empty());
 }
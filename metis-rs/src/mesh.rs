@@ -0,0 +1,304 @@
+//! Meshes, or hypergraphs, given as a list of elements that each touch a set of
+//! nodes, and the METIS routines for partitioning them. Under the `native` feature
+//! those routines fail with `Error::Unsupported`.
+
+#[cfg(not(feature = "native"))]
+use crate::{check, with_metis};
+use crate::{sys, Error, GraphLike, Options};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+pub struct Mesh<V> {
+    nodes: Vec<V>,
+    eptr: Vec<sys::idx_t>,
+    #[cfg_attr(feature = "native", allow(dead_code))]
+    eind: Vec<sys::idx_t>
+}
+
+impl<V: Clone + Eq + Hash> Mesh<V> {
+    /// Build a mesh from the nodes touched by each element. Nodes are numbered in
+    /// the order they first appear.
+    pub fn new<E: IntoIterator<Item = V>>(elements: impl IntoIterator<Item = E>) -> Self {
+        let mut index = HashMap::new();
+        let mut nodes = Vec::new();
+        let mut eptr = vec![0];
+        let mut eind = Vec::new();
+
+        for element in elements {
+            for v in element {
+                let i = *index.entry(v.clone()).or_insert_with(|| {
+                    nodes.push(v);
+                    nodes.len() - 1
+                });
+                eind.push(i as sys::idx_t);
+            }
+            eptr.push(eind.len() as sys::idx_t);
+        }
+
+        Mesh { nodes, eptr, eind }
+    }
+}
+
+impl<V: Clone + Eq + Hash + Debug> Mesh<V> {
+    pub fn num_elements(&self) -> usize {
+        self.eptr.len() - 1
+    }
+
+    pub fn nodes(&self) -> &[V] {
+        &self.nodes
+    }
+}
+
+#[cfg(feature = "native")]
+impl<V: Clone + Eq + Hash + Debug> Mesh<V> {
+    pub fn dual_graph(&self, _ncommon: usize) -> Result<MeshGraph<usize>, Error> {
+        crate::needs_metis("METIS_MeshToDual")
+    }
+
+    pub fn nodal_graph(&self) -> Result<MeshGraph<V>, Error> {
+        crate::needs_metis("METIS_MeshToNodal")
+    }
+
+    pub fn partition_nodal(&self, _nparts: usize, _options: &Options) -> Result<MeshPartition<V>, Error> {
+        crate::needs_metis("METIS_PartMeshNodal")
+    }
+
+    pub fn partition_dual(&self, _nparts: usize, _ncommon: usize, _options: &Options) -> Result<MeshPartition<V>, Error> {
+        crate::needs_metis("METIS_PartMeshDual")
+    }
+}
+
+#[cfg(not(feature = "native"))]
+impl<V: Clone + Eq + Hash + Debug> Mesh<V> {
+    /// The graph whose vertices are the elements, joined when they share at least
+    /// `ncommon` nodes.
+    pub fn dual_graph(&self, ncommon: usize) -> Result<MeshGraph<usize>, Error> {
+        let mut ne = self.num_elements() as sys::idx_t;
+        let mut nn = self.nodes.len() as sys::idx_t;
+        let mut ncommon = ncommon as sys::idx_t;
+        let mut numflag = 0;
+        let mut xadj = std::ptr::null_mut();
+        let mut adjncy = std::ptr::null_mut();
+        let error = with_metis(|| unsafe {
+            sys::METIS_MeshToDual(
+                &mut ne as *mut _,
+                &mut nn as *mut _,
+                self.eptr.as_ptr() as *mut _,
+                self.eind.as_ptr() as *mut _,
+                &mut ncommon as *mut _,
+                &mut numflag as *mut _,
+                &mut xadj as *mut _,
+                &mut adjncy as *mut _
+            )
+        });
+
+        check(error, "METIS_MeshToDual")?;
+
+        Ok(unsafe { MeshGraph::from_metis((0..self.num_elements()).collect(), xadj, adjncy) })
+    }
+
+    /// The graph on the nodes, joining any two nodes that share an element.
+    pub fn nodal_graph(&self) -> Result<MeshGraph<V>, Error> {
+        let mut ne = self.num_elements() as sys::idx_t;
+        let mut nn = self.nodes.len() as sys::idx_t;
+        let mut numflag = 0;
+        let mut xadj = std::ptr::null_mut();
+        let mut adjncy = std::ptr::null_mut();
+        let error = with_metis(|| unsafe {
+            sys::METIS_MeshToNodal(
+                &mut ne as *mut _,
+                &mut nn as *mut _,
+                self.eptr.as_ptr() as *mut _,
+                self.eind.as_ptr() as *mut _,
+                &mut numflag as *mut _,
+                &mut xadj as *mut _,
+                &mut adjncy as *mut _
+            )
+        });
+
+        check(error, "METIS_MeshToNodal")?;
+
+        Ok(unsafe { MeshGraph::from_metis(self.nodes.clone(), xadj, adjncy) })
+    }
+
+    /// Partition the nodal graph into `nparts` parts, and assign each element to
+    /// one of the parts its nodes lie in.
+    pub fn partition_nodal(&self, nparts: usize, options: &Options) -> Result<MeshPartition<V>, Error> {
+        // METIS divides by the number of nodes and by log2(nparts), so put
+        // everything in part 0 rather than letting it crash on a mesh without
+        // nodes or a single part.
+        if self.nodes.is_empty() || nparts == 1 {
+            return Ok(self.single_part());
+        }
+
+        let mut ne = self.num_elements() as sys::idx_t;
+        let mut nn = self.nodes.len() as sys::idx_t;
+        let mut nparts = nparts as sys::idx_t;
+        let mut objval = 0;
+        let mut epart = vec![0; self.num_elements()];
+        let mut npart = vec![0; self.nodes.len()];
+        let error = with_metis(|| unsafe {
+            sys::METIS_PartMeshNodal(
+                &mut ne as *mut _,
+                &mut nn as *mut _,
+                self.eptr.as_ptr() as *mut _,
+                self.eind.as_ptr() as *mut _,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut nparts as *mut _,
+                std::ptr::null_mut(),
+                options.options.as_ptr() as *mut _,
+                &mut objval as *mut _,
+                epart.as_mut_ptr(),
+                npart.as_mut_ptr()
+            )
+        });
+
+        check(error, "METIS_PartMeshNodal")?;
+
+        Ok(self.partition(objval, &epart, &npart))
+    }
+
+    /// Partition the dual graph, where elements are adjacent when they share at
+    /// least `ncommon` nodes, into `nparts` parts, and assign each node to one of
+    /// the parts of the elements touching it.
+    pub fn partition_dual(&self, nparts: usize, ncommon: usize, options: &Options) -> Result<MeshPartition<V>, Error> {
+        if self.nodes.is_empty() || nparts == 1 {
+            return Ok(self.single_part());
+        }
+
+        let mut ne = self.num_elements() as sys::idx_t;
+        let mut nn = self.nodes.len() as sys::idx_t;
+        let mut ncommon = ncommon as sys::idx_t;
+        let mut nparts = nparts as sys::idx_t;
+        let mut objval = 0;
+        let mut epart = vec![0; self.num_elements()];
+        let mut npart = vec![0; self.nodes.len()];
+        let error = with_metis(|| unsafe {
+            sys::METIS_PartMeshDual(
+                &mut ne as *mut _,
+                &mut nn as *mut _,
+                self.eptr.as_ptr() as *mut _,
+                self.eind.as_ptr() as *mut _,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut ncommon as *mut _,
+                &mut nparts as *mut _,
+                std::ptr::null_mut(),
+                options.options.as_ptr() as *mut _,
+                &mut objval as *mut _,
+                epart.as_mut_ptr(),
+                npart.as_mut_ptr()
+            )
+        });
+
+        check(error, "METIS_PartMeshDual")?;
+
+        Ok(self.partition(objval, &epart, &npart))
+    }
+
+    fn single_part(&self) -> MeshPartition<V> {
+        self.partition(0, &vec![0; self.num_elements()], &vec![0; self.nodes.len()])
+    }
+
+    fn partition(&self, objval: sys::idx_t, epart: &[sys::idx_t], npart: &[sys::idx_t]) -> MeshPartition<V> {
+        MeshPartition {
+            elements: epart.iter().map(|&p| p as usize).collect(),
+            nodes: self.nodes.iter().cloned()
+                .zip(npart.iter().map(|&p| p as usize))
+                .collect(),
+            objective: objval as usize
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct MeshPartition<V> {
+    /// The part of each element, in the order the elements were given.
+    pub elements: Vec<usize>,
+    /// The part of each node.
+    pub nodes: Vec<(V, usize)>,
+    /// The edge-cut or communication volume of the partition, depending on the
+    /// objective chosen in `Options`.
+    pub objective: usize
+}
+
+/// A graph derived from a mesh, which can be handed back to `Graph::new`.
+#[derive(Debug,Clone)]
+pub struct MeshGraph<V> {
+    vertices: Vec<V>,
+    index: HashMap<V, usize>,
+    xadj: Vec<usize>,
+    adjncy: Vec<usize>
+}
+
+#[cfg(not(feature = "native"))]
+impl<V: Clone + Eq + Hash> MeshGraph<V> {
+    /// Copy a graph allocated by METIS and free the original arrays.
+    unsafe fn from_metis(vertices: Vec<V>, xadj: *mut sys::idx_t, adjncy: *mut sys::idx_t) -> Self {
+        let (xadj_copy, adjncy_copy) = if xadj.is_null() || adjncy.is_null() {
+            (vec![0; vertices.len() + 1], Vec::new())
+        } else {
+            let n = vertices.len();
+            let xadj_copy = std::slice::from_raw_parts(xadj, n + 1).iter().map(|&x| x as usize).collect::<Vec<_>>();
+            let adjncy_copy = std::slice::from_raw_parts(adjncy, xadj_copy[n]).iter().map(|&x| x as usize).collect();
+            (xadj_copy, adjncy_copy)
+        };
+        with_metis(|| {
+            sys::METIS_Free(xadj as *mut _);
+            sys::METIS_Free(adjncy as *mut _);
+        });
+
+        let index = vertices.iter().cloned().enumerate().map(|(i, v)| (v, i)).collect();
+        MeshGraph { vertices, index, xadj: xadj_copy, adjncy: adjncy_copy }
+    }
+}
+
+impl<V: Clone + Eq + Hash + Debug> GraphLike for MeshGraph<V> {
+    type Vertex = V;
+
+    fn vertices(&self) -> Vec<Self::Vertex> {
+        self.vertices.clone()
+    }
+
+    fn has_edge(&self, a: Self::Vertex, b: Self::Vertex) -> bool {
+        let (a, b) = (self.index[&a], self.index[&b]);
+        self.adjncy[self.xadj[a]..self.xadj[a + 1]].contains(&b)
+    }
+
+    fn neighbors(&self, a: Self::Vertex) -> Vec<Self::Vertex> {
+        let a = self.index[&a];
+        self.adjncy[self.xadj[a]..self.xadj[a + 1]]
+            .iter()
+            .map(|&b| self.vertices[b].clone())
+            .collect()
+    }
+}
+
+#[cfg(not(feature = "native"))]
+#[test]
+fn empty_mesh_test() {
+    let options = Options::default();
+    for elements in [Vec::new(), vec![Vec::new(); 3]] {
+        let mesh = Mesh::<usize>::new(elements);
+        for part in [mesh.partition_nodal(2, &options), mesh.partition_dual(2, 1, &options)] {
+            let part = part.unwrap();
+            assert_eq!(part.elements, vec![0; mesh.num_elements()]);
+            assert!(part.nodes.is_empty() && part.objective == 0);
+        }
+    }
+}
+
+#[cfg(not(feature = "native"))]
+#[test]
+fn single_part_mesh_test() {
+    let mesh = Mesh::new(vec![vec![0, 1, 2], vec![1, 2, 3], vec![2, 3, 4]]);
+    let options = Options::default();
+    for part in [mesh.partition_nodal(1, &options), mesh.partition_dual(1, 1, &options)] {
+        let part = part.unwrap();
+        assert_eq!(part.elements, vec![0; 3]);
+        assert_eq!(part.nodes, (0..5).map(|v| (v, 0)).collect::<Vec<_>>());
+        assert_eq!(part.objective, 0);
+    }
+}
@@ -0,0 +1,541 @@
+//! A pure-Rust replacement for `METIS_ComputeVertexSeparator`, following the same
+//! multilevel scheme: coarsen by heavy-edge matching, grow an initial separator on
+//! the coarsest graph, then project it back up while refining it with
+//! Fiduccia–Mattheyses style moves.
+//!
+//! Vertices may carry several weights. The separator is measured by the first,
+//! while both halves must stay within the imbalance limit for every one.
+
+use crate::{sys, Options};
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Stop coarsening once the graph has at most this many vertices.
+const COARSEST_SIZE: usize = 100;
+/// Number of initial separators grown on the coarsest graph.
+const INITIAL_TRIES: usize = 8;
+/// Number of moves without improvement before a refinement pass gives up.
+const MAX_BAD_MOVES: usize = 64;
+
+const LEFT: u8 = 0;
+const RIGHT: u8 = 1;
+const SEP: u8 = 2;
+
+struct Level {
+    xadj: Vec<usize>,
+    adjncy: Vec<usize>,
+    adjwgt: Vec<i64>,
+    vwgt: Vec<i64>,
+    ncon: usize
+}
+
+impl Level {
+    fn len(&self) -> usize {
+        self.xadj.len() - 1
+    }
+
+    fn neighbors(&self, v: usize) -> &[usize] {
+        &self.adjncy[self.xadj[v]..self.xadj[v + 1]]
+    }
+
+    fn edges(&self, v: usize) -> impl Iterator<Item = (usize, i64)> + '_ {
+        let range = self.xadj[v]..self.xadj[v + 1];
+        self.adjncy[range.clone()].iter().copied().zip(self.adjwgt[range].iter().copied())
+    }
+
+    fn weights(&self, v: usize) -> &[i64] {
+        &self.vwgt[v * self.ncon..(v + 1) * self.ncon]
+    }
+
+    /// The cost of putting `v` in the separator.
+    fn size(&self, v: usize) -> i64 {
+        self.vwgt[v * self.ncon]
+    }
+
+    fn total(&self) -> Vec<i64> {
+        let mut total = vec![0; self.ncon];
+        for v in 0..self.len() {
+            for (t, w) in total.iter_mut().zip(self.weights(v)) {
+                *t += w;
+            }
+        }
+        total
+    }
+}
+
+/// The balance constraints and the number of refinement passes.
+struct Limits {
+    total: Vec<i64>,
+    maxpwgt: Vec<i64>,
+    niter: usize
+}
+
+/// The weight of the two halves and the separator, for every constraint.
+struct Sides([Vec<i64>; 3]);
+
+impl Sides {
+    fn new(g: &Level, side: &[u8]) -> Self {
+        let mut sides = Sides([vec![0; g.ncon], vec![0; g.ncon], vec![0; g.ncon]]);
+        for (v, &s) in side.iter().enumerate() {
+            sides.add(g, v, s);
+        }
+        sides
+    }
+
+    fn add(&mut self, g: &Level, v: usize, s: u8) {
+        for (p, w) in self.0[s as usize].iter_mut().zip(g.weights(v)) {
+            *p += w;
+        }
+    }
+
+    fn remove(&mut self, g: &Level, v: usize, s: u8) {
+        for (p, w) in self.0[s as usize].iter_mut().zip(g.weights(v)) {
+            *p -= w;
+        }
+    }
+
+    /// Whether `v` can join side `to` without breaking any constraint.
+    fn fits(&self, g: &Level, v: usize, to: u8, limits: &Limits) -> bool {
+        self.0[to as usize].iter()
+            .zip(g.weights(v))
+            .zip(&limits.maxpwgt)
+            .all(|((p, w), m)| p + w <= *m)
+    }
+
+    fn separator(&self) -> i64 {
+        self.0[SEP as usize][0]
+    }
+
+    fn feasible(&self, limits: &Limits) -> bool {
+        (0..limits.total.len()).all(|c| self.0[0][c].max(self.0[1][c]) <= limits.maxpwgt[c])
+    }
+
+    /// The heavier half's share of the total, in thousandths, for the worst
+    /// constraint.
+    fn imbalance(&self, limits: &Limits) -> i64 {
+        (0..limits.total.len())
+            .map(|c| self.0[0][c].max(self.0[1][c]) * 1000 / limits.total[c].max(1))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The half holding the smaller share of the total, summed over constraints.
+    fn lighter(&self, limits: &Limits) -> u8 {
+        let share = |s: usize| (0..limits.total.len())
+            .map(|c| self.0[s][c] * 1000 / limits.total[c].max(1))
+            .sum::<i64>();
+        if share(0) <= share(1) { LEFT } else { RIGHT }
+    }
+}
+
+/// A small xorshift generator, so runs are reproducible from `Options::seed`
+/// without pulling in a dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: i64) -> Self {
+        Rng((seed as u64 ^ 0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, xs: &mut [T]) {
+        for i in (1..xs.len()).rev() {
+            xs.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+fn option(options: &Options, option: sys::moptions_et, default: i64) -> i64 {
+    match options.options[option as usize] {
+        v if v < 0 => default,
+        v => v as i64
+    }
+}
+
+fn level(xadj: &[sys::idx_t], adjncy: &[sys::idx_t], vwgt: &[sys::idx_t], ncon: usize) -> Level {
+    Level {
+        xadj: xadj.iter().map(|&x| x as usize).collect(),
+        adjncy: adjncy.iter().map(|&x| x as usize).collect(),
+        adjwgt: vec![1; adjncy.len()],
+        vwgt: vwgt.iter().map(|&w| w as i64).collect(),
+        ncon
+    }
+}
+
+/// The heaviest either half may become for each constraint and the number of
+/// refinement passes, using the same defaults as METIS does for vertex separators.
+/// An imbalance vector in `options` overrides `ufactor`.
+fn limits(graph: &Level, options: &Options) -> Limits {
+    let ufactor = option(options, sys::moptions_et_METIS_OPTION_UFACTOR, 200);
+    let total = graph.total();
+    let maxpwgt = total.iter()
+        .enumerate()
+        .map(|(c, &t)| {
+            let ub = match &options.ubvec {
+                Some(ubvec) => ubvec[c] as f64,
+                None => (1000 + ufactor) as f64 / 1000.0
+            };
+            ((ub * t as f64 / 2.0) as i64).max((t + 1) / 2)
+        })
+        .collect();
+
+    Limits {
+        total,
+        maxpwgt,
+        niter: option(options, sys::moptions_et_METIS_OPTION_NITER, 10) as usize
+    }
+}
+
+/// Compute a vertex separator, returning the side of each vertex with the same
+/// encoding as METIS: 0 and 1 for the two halves and 2 for the separator.
+pub(crate) fn vertex_separator(xadj: &[sys::idx_t], adjncy: &[sys::idx_t], vwgt: &[sys::idx_t], ncon: usize, options: &Options) -> Vec<sys::idx_t> {
+    let graph = level(xadj, adjncy, vwgt, ncon);
+
+    if graph.len() == 0 {
+        return Vec::new();
+    }
+
+    let mut rng = Rng::new(option(options, sys::moptions_et_METIS_OPTION_SEED, 0));
+    let nseps = option(options, sys::moptions_et_METIS_OPTION_NSEPS, 1).max(1) as usize;
+    let limits = limits(&graph, options);
+
+    let mut levels = vec![graph];
+    let mut cmaps = Vec::new();
+    while levels.last().unwrap().len() > COARSEST_SIZE {
+        let fine = levels.last().unwrap();
+        let (coarse, cmap) = coarsen(fine, &mut rng);
+        if coarse.len() * 20 > fine.len() * 19 {
+            break;
+        }
+        levels.push(coarse);
+        cmaps.push(cmap);
+    }
+
+    let mut best: Option<((bool, i64, i64), Vec<u8>)> = None;
+    for _ in 0..nseps {
+        let coarsest = levels.last().unwrap();
+        let mut side = (0..INITIAL_TRIES)
+            .map(|_| {
+                let mut side = grow(coarsest, &limits, &mut rng);
+                refine(coarsest, &mut side, &limits);
+                (score(coarsest, &side, &limits), side)
+            })
+            .min_by_key(|(score, _)| *score)
+            .unwrap()
+            .1;
+
+        for (level, cmap) in levels.iter().zip(&cmaps).rev() {
+            side = cmap.iter().map(|&c| side[c]).collect();
+            refine(level, &mut side, &limits);
+        }
+
+        let score = score(&levels[0], &side, &limits);
+        if best.as_ref().is_none_or(|(best, _)| score < *best) {
+            best = Some((score, side));
+        }
+    }
+
+    best.unwrap().1.into_iter().map(|s| s as sys::idx_t).collect()
+}
+
+/// Refine a valid separator in place without coarsening.
+pub(crate) fn refine_separator(xadj: &[sys::idx_t], adjncy: &[sys::idx_t], vwgt: &[sys::idx_t], ncon: usize, part: &mut [sys::idx_t], options: &Options) {
+    let graph = level(xadj, adjncy, vwgt, ncon);
+    let limits = limits(&graph, options);
+    let mut side = part.iter().map(|&p| p as u8).collect::<Vec<_>>();
+
+    refine(&graph, &mut side, &limits);
+
+    for (p, s) in part.iter_mut().zip(side) {
+        *p = s as sys::idx_t;
+    }
+}
+
+/// Collapse a heavy-edge matching of `g`, returning the coarser graph and the
+/// coarse vertex each fine vertex was merged into.
+fn coarsen(g: &Level, rng: &mut Rng) -> (Level, Vec<usize>) {
+    let n = g.len();
+    let maxvwgt = g.total()
+        .into_iter()
+        .map(|t| (3 * t / (2 * COARSEST_SIZE as i64)).max(1))
+        .collect::<Vec<_>>();
+
+    let mut order = (0..n).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+
+    let mut matched = vec![usize::MAX; n];
+    for v in order {
+        if matched[v] != usize::MAX {
+            continue;
+        }
+
+        let mut mate = v;
+        let mut heaviest = 0;
+        for (u, w) in g.edges(v) {
+            let light = g.weights(v).iter()
+                .zip(g.weights(u))
+                .zip(&maxvwgt)
+                .all(|((a, b), m)| a + b <= *m);
+            if matched[u] == usize::MAX && u != v && light && w > heaviest {
+                mate = u;
+                heaviest = w;
+            }
+        }
+
+        matched[v] = mate;
+        matched[mate] = v;
+    }
+
+    let mut cmap = vec![usize::MAX; n];
+    let mut cn = 0;
+    for v in 0..n {
+        if cmap[v] == usize::MAX {
+            cmap[v] = cn;
+            cmap[matched[v]] = cn;
+            cn += 1;
+        }
+    }
+
+    let mut coarse = Level {
+        xadj: vec![0],
+        adjncy: Vec::new(),
+        adjwgt: Vec::new(),
+        vwgt: vec![0; cn * g.ncon],
+        ncon: g.ncon
+    };
+    let mut slot = vec![usize::MAX; cn];
+
+    // Coarse vertices are numbered by their smaller member, so visiting those in
+    // order emits the coarse adjacency lists in order too.
+    for v in (0..n).filter(|&v| matched[v] >= v) {
+        let c = cmap[v];
+        let start = coarse.adjncy.len();
+        let members = if matched[v] == v { vec![v] } else { vec![v, matched[v]] };
+
+        for m in members {
+            for (k, w) in g.weights(m).iter().enumerate() {
+                coarse.vwgt[c * g.ncon + k] += w;
+            }
+
+            for (u, w) in g.edges(m) {
+                let cu = cmap[u];
+                if cu == c {
+                    continue;
+                }
+
+                if slot[cu] != usize::MAX && slot[cu] >= start {
+                    coarse.adjwgt[slot[cu]] += w;
+                } else {
+                    slot[cu] = coarse.adjncy.len();
+                    coarse.adjncy.push(cu);
+                    coarse.adjwgt.push(w);
+                }
+            }
+        }
+
+        coarse.xadj.push(coarse.adjncy.len());
+    }
+
+    (coarse, cmap)
+}
+
+/// Grow one half breadth-first from a random vertex until it holds half of the
+/// weight for some constraint, then take the boundary of the other half as the
+/// separator.
+fn grow(g: &Level, limits: &Limits, rng: &mut Rng) -> Vec<u8> {
+    let n = g.len();
+    let mut side = vec![RIGHT; n];
+    let mut queued = vec![false; n];
+    let mut queue = VecDeque::new();
+    let mut weight = vec![0; g.ncon];
+
+    let mut order = (0..n).collect::<Vec<_>>();
+    rng.shuffle(&mut order);
+    let mut seeds = order.into_iter();
+
+    while !weight.iter().zip(&limits.total).any(|(&w, &t)| t > 0 && 2 * w >= t) {
+        let v = match queue.pop_front() {
+            Some(v) => v,
+            None => match seeds.find(|&v| !queued[v]) {
+                Some(v) => {
+                    queued[v] = true;
+                    v
+                }
+                None => break
+            }
+        };
+
+        side[v] = LEFT;
+        for (a, w) in weight.iter_mut().zip(g.weights(v)) {
+            *a += w;
+        }
+        for &u in g.neighbors(v) {
+            if !queued[u] {
+                queued[u] = true;
+                queue.push_back(u);
+            }
+        }
+    }
+
+    for v in 0..n {
+        if side[v] == RIGHT && g.neighbors(v).iter().any(|&u| side[u] == LEFT) {
+            side[v] = SEP;
+        }
+    }
+
+    side
+}
+
+/// Order separators by balance feasibility, then separator weight, then balance.
+fn score(g: &Level, side: &[u8], limits: &Limits) -> (bool, i64, i64) {
+    let sides = Sides::new(g, side);
+    (!sides.feasible(limits), sides.separator(), sides.imbalance(limits))
+}
+
+/// The decrease in separator weight from moving separator vertex `v` to `to`,
+/// which pulls its neighbours on the other side into the separator.
+fn gain(g: &Level, side: &[u8], v: usize, to: u8) -> i64 {
+    let other = 1 - to;
+    g.size(v) - g.neighbors(v).iter().filter(|&&u| side[u] == other).map(|&u| g.size(u)).sum::<i64>()
+}
+
+fn refine(g: &Level, side: &mut [u8], limits: &Limits) {
+    for _ in 0..limits.niter {
+        if !refine_pass(g, side, limits) {
+            break;
+        }
+    }
+}
+
+/// One pass of separator refinement. Moves are made greedily, allowing a run of
+/// uphill moves to escape local minima, and the pass is then rolled back to the
+/// best separator seen. Returns whether the separator improved.
+fn refine_pass(g: &Level, side: &mut [u8], limits: &Limits) -> bool {
+    fn push(heaps: &mut [BinaryHeap<(i64, usize)>; 2], g: &Level, side: &[u8], v: usize) {
+        for to in [LEFT, RIGHT] {
+            heaps[to as usize].push((gain(g, side, v, to), v));
+        }
+    }
+
+    let n = g.len();
+    let mut sides = Sides::new(g, side);
+    let mut locked = vec![false; n];
+    let mut heaps = [BinaryHeap::new(), BinaryHeap::new()];
+    for v in 0..n {
+        if side[v] == SEP {
+            push(&mut heaps, g, side, v);
+        }
+    }
+
+    let mut moves: Vec<(usize, u8, Vec<usize>)> = Vec::new();
+    let mut best = (sides.separator(), sides.imbalance(limits));
+    let mut best_len = 0;
+
+    while moves.len() - best_len <= MAX_BAD_MOVES {
+        let lighter = sides.lighter(limits);
+        let mut next = None;
+        'search: for to in [lighter, 1 - lighter] {
+            while let Some((g_v, v)) = heaps[to as usize].pop() {
+                // Entries are never updated in place, so skip any that are stale.
+                if locked[v] || side[v] != SEP || gain(g, side, v, to) != g_v {
+                    continue;
+                }
+                if !sides.fits(g, v, to, limits) {
+                    continue;
+                }
+                next = Some((v, to));
+                break 'search;
+            }
+        }
+
+        let Some((v, to)) = next else { break };
+        let other = 1 - to;
+
+        side[v] = to;
+        locked[v] = true;
+        sides.remove(g, v, SEP);
+        sides.add(g, v, to);
+
+        let pulled = g.neighbors(v).iter().copied().filter(|&u| side[u] == other).collect::<Vec<_>>();
+        for &u in &pulled {
+            side[u] = SEP;
+            sides.remove(g, u, other);
+            sides.add(g, u, SEP);
+        }
+
+        for &c in std::iter::once(&v).chain(&pulled) {
+            for &w in std::iter::once(&c).chain(g.neighbors(c)) {
+                if side[w] == SEP && !locked[w] {
+                    push(&mut heaps, g, side, w);
+                }
+            }
+        }
+
+        moves.push((v, to, pulled));
+
+        let current = (sides.separator(), sides.imbalance(limits));
+        if current < best {
+            best = current;
+            best_len = moves.len();
+        }
+    }
+
+    while moves.len() > best_len {
+        let (v, to, pulled) = moves.pop().unwrap();
+        for u in pulled {
+            side[u] = 1 - to;
+        }
+        side[v] = SEP;
+    }
+
+    best_len > 0
+}
+
+#[test]
+fn vertex_separator_test() {
+    use crate::Graph;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let mut grid = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let nodes = (0..900).map(|_| grid.add_node(())).collect::<Vec<_>>();
+    for i in 0..30 {
+        for j in 0..30 {
+            if i + 1 < 30 {
+                grid.add_edge(nodes[i * 30 + j], nodes[(i + 1) * 30 + j], ());
+            }
+            if j + 1 < 30 {
+                grid.add_edge(nodes[i * 30 + j], nodes[i * 30 + j + 1], ());
+            }
+        }
+    }
+    let mut gnp = petgraph::graph::UnGraph::<(), ()>::new_undirected();
+    let nodes = (0..300).map(|_| gnp.add_node(())).collect::<Vec<_>>();
+    for a in 0..300 {
+        for b in a + 1..300 {
+            if rng.gen::<f64>() < 0.02 {
+                gnp.add_edge(nodes[a], nodes[b], ());
+            }
+        }
+    }
+
+    for graph in [grid, gnp] {
+        let sep = Graph::new(&graph).vertex_separator(&Options::default()).unwrap();
+        sep.validate(&graph).unwrap();
+        assert!(!sep.left.is_empty() && !sep.right.is_empty());
+
+        let part = Graph::new(&graph).partition(2, &Options::default());
+        assert_eq!(part.unwrap_err(), crate::Error::Unsupported { function: "METIS_PartGraphKway" });
+
+        let weighted = graph.map(|_, _| [1, rng.gen_range(0..2usize)], |_, _| ());
+        let options = Options::default().max_imbalances(vec![1.1, 1.1]);
+        let sep = Graph::new_multi_weighted(&weighted).unwrap().vertex_separator(&options).unwrap();
+        sep.validate(&weighted).unwrap();
+        assert!(!sep.left.is_empty() && !sep.right.is_empty());
+    }
+}
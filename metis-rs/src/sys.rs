@@ -1276,24 +1276,26 @@ pub const moptions_et_METIS_OPTION_NIPARTS: moptions_et = 6;
 pub const moptions_et_METIS_OPTION_NITER: moptions_et = 7;
 pub const moptions_et_METIS_OPTION_NCUTS: moptions_et = 8;
 pub const moptions_et_METIS_OPTION_SEED: moptions_et = 9;
-pub const moptions_et_METIS_OPTION_NO2HOP: moptions_et = 10;
-pub const moptions_et_METIS_OPTION_ONDISK: moptions_et = 11;
-pub const moptions_et_METIS_OPTION_MINCONN: moptions_et = 12;
-pub const moptions_et_METIS_OPTION_CONTIG: moptions_et = 13;
-pub const moptions_et_METIS_OPTION_COMPRESS: moptions_et = 14;
-pub const moptions_et_METIS_OPTION_CCORDER: moptions_et = 15;
-pub const moptions_et_METIS_OPTION_PFACTOR: moptions_et = 16;
-pub const moptions_et_METIS_OPTION_NSEPS: moptions_et = 17;
-pub const moptions_et_METIS_OPTION_UFACTOR: moptions_et = 18;
-pub const moptions_et_METIS_OPTION_NUMBERING: moptions_et = 19;
-pub const moptions_et_METIS_OPTION_DROPEDGES: moptions_et = 20;
-pub const moptions_et_METIS_OPTION_HELP: moptions_et = 21;
-pub const moptions_et_METIS_OPTION_TPWGTS: moptions_et = 22;
-pub const moptions_et_METIS_OPTION_NCOMMON: moptions_et = 23;
-pub const moptions_et_METIS_OPTION_NOOUTPUT: moptions_et = 24;
-pub const moptions_et_METIS_OPTION_BALANCE: moptions_et = 25;
-pub const moptions_et_METIS_OPTION_GTYPE: moptions_et = 26;
-pub const moptions_et_METIS_OPTION_UBVEC: moptions_et = 27;
+pub const moptions_et_METIS_OPTION_ONDISK: moptions_et = 10;
+pub const moptions_et_METIS_OPTION_MINCONN: moptions_et = 11;
+pub const moptions_et_METIS_OPTION_CONTIG: moptions_et = 12;
+pub const moptions_et_METIS_OPTION_COMPRESS: moptions_et = 13;
+pub const moptions_et_METIS_OPTION_CCORDER: moptions_et = 14;
+pub const moptions_et_METIS_OPTION_PFACTOR: moptions_et = 15;
+pub const moptions_et_METIS_OPTION_NSEPS: moptions_et = 16;
+pub const moptions_et_METIS_OPTION_UFACTOR: moptions_et = 17;
+pub const moptions_et_METIS_OPTION_NUMBERING: moptions_et = 18;
+pub const moptions_et_METIS_OPTION_DROPEDGES: moptions_et = 19;
+pub const moptions_et_METIS_OPTION_NO2HOP: moptions_et = 20;
+pub const moptions_et_METIS_OPTION_TWOHOP: moptions_et = 21;
+pub const moptions_et_METIS_OPTION_FAST: moptions_et = 22;
+pub const moptions_et_METIS_OPTION_HELP: moptions_et = 23;
+pub const moptions_et_METIS_OPTION_TPWGTS: moptions_et = 24;
+pub const moptions_et_METIS_OPTION_NCOMMON: moptions_et = 25;
+pub const moptions_et_METIS_OPTION_NOOUTPUT: moptions_et = 26;
+pub const moptions_et_METIS_OPTION_BALANCE: moptions_et = 27;
+pub const moptions_et_METIS_OPTION_GTYPE: moptions_et = 28;
+pub const moptions_et_METIS_OPTION_UBVEC: moptions_et = 29;
 #[doc = " Options codes (i.e., options[])"]
 pub type moptions_et = ::std::os::raw::c_uint;
 pub const mptype_et_METIS_PTYPE_RB: mptype_et = 0;
@@ -0,0 +1,110 @@
+use roots::{find_root_brent, SearchError, SimpleConvergency};
+use std::fmt;
+
+/// How many times the initial bracket [1, 10] is widened before giving up.
+const MAX_EXPANSIONS: usize = 32;
+
+#[derive(Debug, PartialEq)]
+pub enum AlphaError {
+    /// One sub instance keeps every non-Clifford spider, so the decomposition
+    /// never makes progress and the cost is unbounded.
+    NoProgress { a: usize, b: usize },
+    /// No strict sign change was found even after widening the bracket, so f has
+    /// no root (or one f32 cannot resolve).
+    NoBracket { a: usize, b: usize, num_diag: usize },
+    /// Brent's method failed inside a valid bracket.
+    Search(SearchError),
+}
+
+impl fmt::Display for AlphaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlphaError::NoProgress { a, b } => {
+                write!(f, "a sub instance removes no non-Clifford spiders (a = {}, b = {})", a, b)
+            }
+            AlphaError::NoBracket { a, b, num_diag } => {
+                write!(f, "no root to bracket for a = {}, b = {}, {} diagrams", a, b, num_diag)
+            }
+            AlphaError::Search(e) => write!(f, "root finding failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AlphaError {}
+
+/// The exponent alpha such that splitting into 2^`num_diag` pairs of instances,
+/// each removing `a` and `b` non-Clifford spiders, costs 2^(alpha t) for t
+/// non-Clifford spiders.
+pub fn alpha(a: usize, b: usize, num_diag: usize) -> Result<f32, AlphaError> {
+    // With a = 0, f below no longer grows with x, so there is no meaningful root.
+    if a == 0 {
+        return Err(AlphaError::NoProgress { a, b });
+    }
+
+    // With a = b the last term of f below is the constant ln 3, so the root is
+    // x = 2^((num_diag - log2 3) / a) and needs no search.
+    if a == b {
+        return Ok((num_diag as f32 - 3f32.log2()) / a as f32);
+    }
+
+    let f = |x: f32| {
+        a as f32 * x.ln() - (num_diag as f32) / std::f32::consts::LOG2_E
+            + (1.0 + x.powi(a as i32 - b as i32)).ln_1p()
+    };
+
+    let (mut lo, mut hi) = (1.0f32, 10.0f32);
+    for _ in 0..MAX_EXPANSIONS {
+        if f(lo) < 0.0 {
+            break;
+        }
+        lo /= 2.0;
+    }
+    for _ in 0..MAX_EXPANSIONS {
+        if f(hi) > 0.0 {
+            break;
+        }
+        hi *= 2.0;
+    }
+
+    // A bracket end where f is exactly 0 is usually rounding rather than a root:
+    // with b = 2a and no diagrams, f(x) = ln(1 + 2x^a) is positive everywhere.
+    if !(f(lo) < 0.0 && f(hi) > 0.0) {
+        return Err(AlphaError::NoBracket { a, b, num_diag });
+    }
+
+    let t = find_root_brent(
+        lo,
+        hi,
+        f,
+        &mut SimpleConvergency {
+            eps: 0.001,
+            max_iter: 100,
+        },
+    )
+    .map_err(AlphaError::Search)?;
+    Ok(t.log2())
+}
+
+#[test]
+fn alpha_test() {
+    assert!(alpha(10, 20, 3).unwrap().is_finite());
+    assert_eq!(alpha(0, 5, 3), Err(AlphaError::NoProgress { a: 0, b: 5 }));
+    assert_eq!(alpha(0, 0, 0), Err(AlphaError::NoProgress { a: 0, b: 0 }));
+    assert_eq!(alpha(5, 5, 200), Ok((200.0 - 3f32.log2()) / 5.0));
+    // The closed form is a root of the same equation the search solves.
+    let x = 2f32.powf(alpha(4, 4, 6).unwrap());
+    assert!((4.0 * x.ln() - 6.0 / std::f32::consts::LOG2_E + 2f32.ln_1p()).abs() < 1e-4);
+    assert_eq!(alpha(1, 1, 200), Ok(200.0 - 3f32.log2()));
+
+    // Roots outside the initial bracket [1, 10] are still found.
+    let x = 2f32.powf(alpha(100, 150, 0).unwrap());
+    assert!(x < 1.0);
+    assert!((100.0 * x.ln() + (1.0 + x.powi(-50)).ln_1p()).abs() < 1e-2);
+    assert!(alpha(1, 2, 8).unwrap() > 10f32.log2());
+
+    // f(x) = ln(1 + 2x^a) has no root, even though f32 rounds f(0.5) to 0.
+    assert_eq!(
+        alpha(100, 200, 0),
+        Err(AlphaError::NoBracket { a: 100, b: 200, num_diag: 0 })
+    );
+}
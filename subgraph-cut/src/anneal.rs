@@ -1,11 +1,15 @@
-use crate::bigraph::BiGraph;
+use crate::alpha::{alpha, AlphaError};
+use crate::bigraph::{sides, VertexIndex};
+use crate::incremental::{CutState, Update};
 use metis::VertexSeparator;
 use petgraph as px;
-use px::stable_graph::{NodeIndex, StableUnGraph};
+use px::stable_graph::StableUnGraph;
+use quizx::graph::{EType, GraphLike as _};
 use rand::seq::IteratorRandom;
-use roots::{find_root_brent, SimpleConvergency};
+use serde::Serialize;
 use std::cmp;
 use std::collections::HashSet;
+use std::time::Instant;
 
 #[derive(Debug, Clone)]
 pub struct GeometricSeries {
@@ -39,9 +43,118 @@ impl Iterator for GeometricSeries {
     }
 }
 
-pub struct ComplementFinder<R: rand::Rng, T: Iterator<Item = f32>> {
-    pub graph: StableUnGraph<(), ()>,
-    pub current: HashSet<px::graph::NodeIndex>,
+/// The graph operations the annealer needs on top of `metis::GraphLike`.
+pub trait AnnealGraph: metis::GraphLike<Vertex: VertexIndex + Send + Sync> + Clone {
+    /// Remove the edge between `a` and `b` if there is one, and add it otherwise.
+    fn toggle_edge(&mut self, a: Self::Vertex, b: Self::Vertex);
+    /// Whether `v` counts towards the T-count.
+    fn is_non_clifford(&self, v: Self::Vertex) -> bool;
+}
+
+/// Petgraph graphs with each node weighted by whether it is non-Clifford, as
+/// produced by `GraphUtils::to_petgraph`.
+impl AnnealGraph for StableUnGraph<bool, ()> {
+    fn toggle_edge(&mut self, a: Self::Vertex, b: Self::Vertex) {
+        if let Some(e) = self.find_edge(a, b) {
+            self.remove_edge(e);
+        } else {
+            self.add_edge(a, b, ());
+        }
+    }
+
+    fn is_non_clifford(&self, v: Self::Vertex) -> bool {
+        self[v]
+    }
+}
+
+/// ZX-diagrams, annealed in place so that solutions are quizx vertices. Edges are
+/// toggled without any scalar bookkeeping, so the annealed graph only describes
+/// connectivity; `subgraph_complement` gives the exact terms.
+macro_rules! impl_anneal_quizx {
+    ($graph:ty) => {
+        impl AnnealGraph for $graph {
+            fn toggle_edge(&mut self, a: Self::Vertex, b: Self::Vertex) {
+                if self.connected(a, b) {
+                    self.remove_edge(a, b);
+                } else {
+                    self.add_edge_with_type(a, b, EType::H);
+                }
+            }
+
+            fn is_non_clifford(&self, v: Self::Vertex) -> bool {
+                *self.phase(v).denom() > 2
+            }
+        }
+    };
+}
+
+impl_anneal_quizx!(quizx::vec_graph::Graph);
+impl_anneal_quizx!(quizx::hash_graph::Graph);
+
+/// How the edges between the bigger side of a separator and its cut are turned
+/// into subgraph complements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CoverStrategy {
+    /// Rank decomposition of the biadjacency matrix, falling back to a vertex cut
+    /// when the rank equals the size of either side.
+    Rank,
+    /// One complement per vertex of a minimum vertex cover (König).
+    Konig,
+    /// Whichever of the two has the lower fitness.
+    Best,
+}
+
+/// The outcome of `ComplementFinder::run`, for aggregating experiments.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunReport {
+    /// Alpha of the initial separator used as a plain vertex cut, if defined.
+    pub initial_alpha: Option<f32>,
+    /// The best fitness, which is alpha plus the barrier on the number of complements.
+    pub best_fitness: f32,
+    /// Alpha of the best state found, if defined.
+    pub alpha: Option<f32>,
+    /// Sizes of the two sides and the cut of the best separator.
+    pub left: usize,
+    pub right: usize,
+    pub cut: usize,
+    /// Number of subgraph complements, including those of earlier rounds.
+    pub complements: usize,
+    /// How many of the subgraph complements act as vertex cuts.
+    pub vertex_cuts: usize,
+    /// Number of subgraph complements needed for the same cut using vertex cuts only.
+    pub vertex_cut: usize,
+    /// Number of vertices in the two sub instances.
+    pub split: (isize, usize),
+    pub trace: Vec<TraceStep>,
+    /// Wall-clock time of the run.
+    pub seconds: f64,
+}
+
+/// The state after one annealing step.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceStep {
+    pub step: usize,
+    pub temperature: f32,
+    pub fitness: f32,
+    /// The probability with which the proposed move was accepted.
+    pub probability: f32,
+    pub complements: usize,
+    pub cut: usize,
+}
+
+/// What a rejected step must restore once its edge toggles are undone.
+enum Undo<G: AnnealGraph> {
+    /// The separator was recomputed, so the previous one and its state come back.
+    Rebuild((VertexSeparator<G>, Vec<Vec<G::Vertex>>), Option<CutState<G::Vertex>>),
+    /// The bipartite graph changed, so its toggles are replayed and the previous
+    /// cover comes back.
+    Update(Vec<Vec<G::Vertex>>),
+    Nothing,
+}
+
+pub struct ComplementFinder<G: AnnealGraph, R: rand::Rng, T: Iterator<Item = f32>> {
+    pub graph: G,
+    pub current: HashSet<G::Vertex>,
     pub fitness: f32,
     imbalance: usize,
     max_nb_complement: usize,
@@ -49,16 +162,20 @@ pub struct ComplementFinder<R: rand::Rng, T: Iterator<Item = f32>> {
     temperature: T,
     depth: usize,
     best_fitness: f32,
-    best_current: HashSet<px::graph::NodeIndex>,
-    best_graph: StableUnGraph<(), ()>,
-    current_cut: (VertexSeparator<StableUnGraph<(), ()>>, Vec<Vec<NodeIndex>>),
-    best_cut: (VertexSeparator<StableUnGraph<(), ()>>, Vec<Vec<NodeIndex>>),
+    best_current: HashSet<G::Vertex>,
+    best_graph: G,
+    current_cut: (VertexSeparator<G>, Vec<Vec<G::Vertex>>),
+    best_cut: (VertexSeparator<G>, Vec<Vec<G::Vertex>>),
+    cut_state: Option<CutState<G::Vertex>>,
+    incremental: bool,
+    cover: CoverStrategy,
+    separator_seeds: usize,
     // vertex_count:usize
 }
 
-impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
+impl<G: AnnealGraph, R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<G, R, T> {
     pub fn new(
-        graph: &StableUnGraph<(), ()>,
+        graph: &G,
         mut rng: R,
         temperature: T,
         depth: usize,
@@ -66,9 +183,10 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
         max_nb_complement: usize,
     ) -> Self {
         let complement = graph
-             .node_indices()
+             .vertices()
+             .into_iter()
              .choose_multiple(&mut rng, 3);
-            // .choose_multiple(&mut rng, graph.node_count() / 2);
+            // .choose_multiple(&mut rng, graph.vertices().len() / 2);
         let mut finder = ComplementFinder {
             graph: graph.clone(),
             current: HashSet::new(),
@@ -84,28 +202,58 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
             current_cut: (VertexSeparator{left : Vec::new(), cut :Vec::new(), right : Vec::new()},Vec::new()),
             // BiGraph { graph: StableUnGraph::default(), left: Vec::new(), right: Vec::new() },
             best_cut: (VertexSeparator{left : Vec::new(), cut :Vec::new(), right : Vec::new()},Vec::new()),
+            cut_state: None,
+            incremental: true,
+            cover: CoverStrategy::Rank,
+            separator_seeds: 1,
         };
         for c in complement {
             finder.toggle_node(c);
         }
-        finder.fitness = finder.fitness();
+        // A degenerate starting state is replaced by the first valid one.
+        finder.fitness = finder.fitness(&[]).0.unwrap_or(f32::INFINITY);
         finder.best_fitness = finder.fitness;
+        finder.best_graph = finder.graph.clone();
+        finder.best_current = finder.current.clone();
+        finder.best_cut = finder.current_cut.clone();
         finder
     }
 
-    fn toggle_node(&mut self, node: px::graph::NodeIndex) {
+    /// Whether to keep the separator across steps for as long as no toggled edge
+    /// crosses it (the default), instead of recomputing it after every step.
+    pub fn incremental(mut self, incremental: bool) -> Self {
+        self.incremental = incremental;
+        self
+    }
+
+    /// How each separator is covered by subgraph complements, `CoverStrategy::Rank`
+    /// by default.
+    pub fn cover_strategy(mut self, cover: CoverStrategy) -> Self {
+        self.cover = cover;
+        self
+    }
+
+    /// How many METIS seeds each separator is computed with, keeping the one with
+    /// the smallest cut and then the best balance. 1 (the default) uses METIS's
+    /// own seed.
+    pub fn separator_seeds(mut self, seeds: usize) -> Self {
+        self.separator_seeds = seeds;
+        self
+    }
+
+    /// Toggle `node` in or out of the complemented set and return the pairs whose
+    /// edge was toggled.
+    fn toggle_node(&mut self, node: G::Vertex) -> Vec<(G::Vertex, G::Vertex)> {
         let mut present = false;
-        for &other in &self.current {
-            if other == node {
+        let mut toggled = Vec::new();
+        for other in &self.current {
+            if *other == node {
                 present = true;
                 continue;
             }
 
-            if let Some(e) = self.graph.find_edge(node, other) {
-                self.graph.remove_edge(e);
-            } else {
-                self.graph.add_edge(node, other, ());
-            }
+            self.graph.toggle_edge(node.clone(), other.clone());
+            toggled.push((node.clone(), other.clone()));
         }
 
         if !present {
@@ -113,21 +261,43 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
         } else {
             self.current.remove(&node);
         }
+
+        toggled
     }
 
     fn step(&mut self, temp: f32) -> f32 {
-        let node = self.graph.node_indices().choose(&mut self.rng).unwrap();
-
-        self.toggle_node(node);
-        let new_fitness = self.fitness();
-
-
+        let node = self.graph.vertices().into_iter().choose(&mut self.rng).unwrap();
+
+        let toggled = self.toggle_node(node.clone());
+        let (fitness, undo) = self.fitness(&toggled);
+
+        // States whose alpha cannot be computed are always rejected.
+        let (accept, prob) = match fitness {
+            Ok(new_fitness) => {
+                let prob = ((new_fitness - self.fitness) as f32 / temp).exp().recip();
+                let accept = self.rng.gen::<f32>() < prob;
+                if accept {
+                    self.fitness = new_fitness;
+                }
+                (accept, prob)
+            }
+            Err(_) => (false, 0.0),
+        };
 
-        let prob = ((new_fitness - self.fitness) as f32 / temp).exp().recip();
-        if self.rng.gen::<f32>() < prob {
-            self.fitness = new_fitness;
-        } else {
+        if !accept {
             self.toggle_node(node);
+            match undo {
+                Undo::Rebuild(cut, state) => {
+                    self.current_cut = cut;
+                    self.cut_state = state;
+                }
+                Undo::Update(sgcs) => {
+                    // Toggling the same edges again undoes the update.
+                    self.cut_state.as_mut().unwrap().update(&toggled);
+                    self.current_cut.1 = sgcs;
+                }
+                Undo::Nothing => {}
+            }
         }
 
         if self.fitness < self.best_fitness {
@@ -140,29 +310,85 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
         prob.min(1.0)
     }
 
-    fn complement_cover(&self) -> (VertexSeparator<StableUnGraph<(), ()>>, Vec<Vec<NodeIndex>>) {
-        let sep = metis::Graph::new(&self.graph)
-            .vertex_separator(&metis::Options::default().max_imbalance(self.imbalance))
-            .unwrap();
-
-        let bg = BiGraph::from_sep(&self.graph, &sep);
+    fn separator(&self) -> VertexSeparator<G> {
+        let graph = metis::Graph::new(&self.graph);
+        let options = metis::Options::default().max_imbalance(self.imbalance);
+        if self.separator_seeds <= 1 {
+            return graph.vertex_separator(&options).unwrap();
+        }
 
-        let sgcs = bg.complement_cover();
+        let seeds = (1..=self.separator_seeds as i32).collect::<Vec<_>>();
+        let score = |sep: &VertexSeparator<G>| (sep.cut.len(), sep.left.len().abs_diff(sep.right.len()));
+        graph.best_separator_over_seeds(&seeds, &options, score).unwrap().1
+    }
 
+    fn complement_cover(&self) -> (VertexSeparator<G>, Vec<Vec<G::Vertex>>) {
+        let sep = self.separator();
+        let (sgcs, _) = self.cover(&sep, &CutState::new(&self.graph, &sep));
         (sep, sgcs)
     }
 
-    fn fitness(&mut self) -> f32 {
-        let (sep, sgcs) = self.complement_cover();
+    /// The subgraph complements the cover strategy picks for `sep`, with their fitness.
+    fn cover(
+        &self,
+        sep: &VertexSeparator<G>,
+        state: &CutState<G::Vertex>,
+    ) -> (Vec<Vec<G::Vertex>>, Result<f32, AlphaError>) {
+        let scored = |sgcs: Vec<Vec<G::Vertex>>| {
+            let fitness = self.score(sep, &sgcs);
+            (sgcs, fitness)
+        };
+
+        match self.cover {
+            CoverStrategy::Rank => scored(state.complement_cover()),
+            CoverStrategy::Konig => scored(state.konig_cover()),
+            CoverStrategy::Best => {
+                let rank = scored(state.complement_cover());
+                let konig = scored(state.konig_cover());
+                match (&rank.1, &konig.1) {
+                    (Ok(r), Ok(k)) if k < r => konig,
+                    (Err(_), Ok(_)) => konig,
+                    _ => rank,
+                }
+            }
+        }
+    }
+
+    fn score(&self, sep: &VertexSeparator<G>, sgcs: &Vec<Vec<G::Vertex>>) -> Result<f32, AlphaError> {
         let barrier = log_barrier(sgcs.len(), self.max_nb_complement)/200.0;
 
-        let temp = improved_alpha_subgraph_complements(&self.graph, &sep, &sgcs, self.depth) + barrier;
+        improved_alpha_subgraph_complements(&self.graph, sep, sgcs, self.depth)
+            .map(|alpha| alpha + barrier)
+    }
 
-        self.current_cut = (sep,sgcs);
+    /// The fitness after the edges between the pairs in `toggled` were toggled.
+    /// The separator is only recomputed when one of them crosses it, and the
+    /// complement cover only when the bipartite graph across it changed. Also
+    /// returns what to restore if the step is rejected.
+    fn fitness(&mut self, toggled: &[(G::Vertex, G::Vertex)]) -> (Result<f32, AlphaError>, Undo<G>) {
+        let update = match &mut self.cut_state {
+            Some(state) if self.incremental => state.update(toggled),
+            _ => Update::Invalid,
+        };
 
-        temp
+        match update {
+            Update::Invalid => {
+                let sep = self.separator();
+                let state = CutState::new(&self.graph, &sep);
+                let (sgcs, fitness) = self.cover(&sep, &state);
+                let cut = std::mem::replace(&mut self.current_cut, (sep, sgcs));
+                let state = self.cut_state.replace(state);
+                (fitness, Undo::Rebuild(cut, state))
+            }
+            Update::Changed => {
+                let (sgcs, fitness) = self.cover(&self.current_cut.0, self.cut_state.as_ref().unwrap());
+                let sgcs = std::mem::replace(&mut self.current_cut.1, sgcs);
+                (fitness, Undo::Update(sgcs))
+            }
+            Update::Unchanged => (self.score(&self.current_cut.0, &self.current_cut.1), Undo::Nothing),
+        }
     }
-    fn old_fitness(&self) -> f32 {
+    fn old_fitness(&self) -> Result<f32, AlphaError> {
         let (sep, sgcs) = self.complement_cover();
         alpha_subgraph_complements(&self.graph, &sep, &sgcs, self.depth)
     }
@@ -175,18 +401,22 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
         self.depth + self.current_cut.0.cut.len()
     }
 
-    pub fn run(&mut self, quiet: bool) {
+    pub fn run(&mut self, quiet: bool) -> RunReport {
+        let start = Instant::now();
         let mut step = 0;
         let mut prob = 1.0;
         let original = self.fitness;
         let initial_cut = self.complement_cover();
+        let initial_alpha = vertex_cut_alpha(&self.graph, &initial_cut,self.depth as isize -1);
 
-        let initial_cut_alpha = vertex_cut_alpha(&initial_cut,self.depth as isize -1);
-
-        println!("Inital alpha with vertex cut : {} with {} vertices", initial_cut_alpha, initial_cut.1.len());
-
-
+        if !quiet {
+            match &initial_alpha {
+                Ok(initial_cut_alpha) => println!("Inital alpha with vertex cut : {} with {} vertices", initial_cut_alpha, initial_cut.1.len()),
+                Err(e) => println!("Inital vertex cut is degenerate: {}", e),
+            }
+        }
 
+        let mut trace = Vec::new();
         while let Some(temp) = self.temperature.next() {
             if !quiet && step % 1000 == 0 {
                 println!(
@@ -195,6 +425,14 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
                 );
             }
             prob = self.step(temp);
+            trace.push(TraceStep {
+                step,
+                temperature: temp,
+                fitness: self.fitness,
+                probability: prob,
+                complements: self.complements(),
+                cut: self.vertex_cut(),
+            });
             step += 1;
         }
 
@@ -203,129 +441,117 @@ impl<R: rand::Rng, T: Iterator<Item = f32>> ComplementFinder<R, T> {
         self.current_cut=self.best_cut.clone();
         self.fitness = self.best_fitness;
 
+        let sep = &self.best_cut.0;
+        let vertex_cuts = vertex_cut_in_subgraph_complement_cut(&self.best_cut);
+        let smaller = sep.left.len().min(sep.right.len());
+        let report = RunReport {
+            initial_alpha: initial_alpha.ok(),
+            best_fitness: self.best_fitness,
+            alpha: improved_alpha_subgraph_complements(&self.graph, sep, &self.best_cut.1, self.depth).ok(),
+            left: sep.left.len(),
+            right: sep.right.len(),
+            cut: sep.cut.len(),
+            complements: self.complements(),
+            vertex_cuts,
+            vertex_cut: self.vertex_cut(),
+            split: (
+                smaller as isize + self.best_cut.1.len() as isize - vertex_cuts as isize,
+                sep.left.len().max(sep.right.len()),
+            ),
+            trace,
+            seconds: start.elapsed().as_secs_f64(),
+        };
+
         if !quiet {
             println!(
                 "final: alpha = {:?}, nb_sugraph_complement : {} of which {} are cuts. \nTo do the same cut with vertex cut + rounds of sugraph complement : {}",
-                improved_alpha_subgraph_complements(&self.graph, &self.best_cut.0, &self.best_cut.1, self.depth),
-                self.complements(),
-                vertex_cut_in_subgraph_complement_cut(&self.best_cut),
-                self.vertex_cut()
-            );
-            println!("the best fitness is {}", self.best_fitness);
-
-            let smaller = self.current_cut.0.left.len().min( self.current_cut.0.right.len());
-            let bigger = self.current_cut.0.left.len().max( self.current_cut.0.right.len());
-            println!(
-                "The split is {} vs {}",
-                smaller as isize + self.best_cut.1.len() as isize - vertex_cut_in_subgraph_complement_cut(&self.best_cut) as isize, 
-                bigger
+                report.alpha,
+                report.complements,
+                report.vertex_cuts,
+                report.vertex_cut
             );
+            println!("the best fitness is {}", report.best_fitness);
+            println!("The split is {} vs {}", report.split.0, report.split.1);
         }
+
+        report
     }
-    pub fn solution_found(&self) -> HashSet<px::graph::NodeIndex>{
+    pub fn solution_found(&self) -> HashSet<G::Vertex>{
         self.current.clone()
     }
 }
 
-fn alpha_subgraph_complements(
-    g: &StableUnGraph<(), ()>,
-    sep: &VertexSeparator<StableUnGraph<(), ()>>,
-    subgraph_complements: &Vec<Vec<NodeIndex>>,
+fn alpha_subgraph_complements<G: AnnealGraph>(
+    g: &G,
+    sep: &VertexSeparator<G>,
+    subgraph_complements: &Vec<Vec<G::Vertex>>,
     nb_initials_subgraph_complements: usize
-) -> f32 {
-    // TODO : Take tcounts into account!
-
+) -> Result<f32, AlphaError> {
     // log2 of number of diagrams
     let num_diag = subgraph_complements.len() + nb_initials_subgraph_complements;
-    let n = g.node_count();
+    let n = tcount(g, g.vertices());
+
+    let (smaller, bigger) = sides(sep);
 
-    // d1 and d2 are the number of vertices in each sub instance
+    // d1 and d2 are the number of non-Clifford spiders in each sub instance
     let ( d1, d2) = (
-        (cmp::min(sep.left.len(), sep.right.len()) + sep.cut.len()),
-        cmp::max(sep.left.len(), sep.right.len()),
+        tcount(g, smaller.iter().chain(&sep.cut)),
+        tcount(g, bigger),
     );
 
-    // a and b are the number of vertices removed
+    // a and b are the number of non-Clifford spiders removed
     let (a, b) = (n - cmp::max(d1, d2), n- cmp::min(d1, d2));
 
-    // println!("a : {} b : {}, num_diag : {} ",a,b, num_diag);
-
-    let f = |x: f32| {
-        a as f32 * x.ln() - (num_diag as f32) / 1.442695
-            + (1.0 + x.powi(a as i32 - b as i32)).ln_1p()
-        // the constant is lg(e)
-    };
-
-    let t = find_root_brent(
-        1.0,
-        10.0,
-        f,
-        &mut SimpleConvergency {
-            eps: 0.001,
-            max_iter: 100,
-        },
-    )
-    .unwrap();
-    t.log2()
+    alpha(a, b, num_diag)
 }
 
 
 
-fn improved_alpha_subgraph_complements(
-    g: &StableUnGraph<(), ()>,
-    sep: &VertexSeparator<StableUnGraph<(), ()>>,
-    subgraph_complements: &Vec<Vec<NodeIndex>>,
+fn improved_alpha_subgraph_complements<G: AnnealGraph>(
+    g: &G,
+    sep: &VertexSeparator<G>,
+    subgraph_complements: &Vec<Vec<G::Vertex>>,
     nb_initials_subgraph_complements: usize,
-) -> f32 {
-    // TODO : Take tcounts into account!
-
+) -> Result<f32, AlphaError> {
     // log2 of number of diagrams
     let num_diag = subgraph_complements.len() + nb_initials_subgraph_complements;
-    let n = g.node_count();
+    let n = tcount(g, g.vertices());
 
+    let (smaller, bigger) = sides(sep);
 
-    // d1 and d2 are the number of vertices in each sub instance
+    // d1 and d2 are the number of non-Clifford spiders in each sub instance
     let ( mut d1, mut d2) = (
-        (cmp::min(sep.left.len(), sep.right.len()) + sep.cut.len()),
-        cmp::max(sep.left.len(), sep.right.len()),
+        tcount(g, smaller.iter().chain(&sep.cut)),
+        tcount(g, bigger),
     );
 
+    let cut = sep.cut.iter().collect::<HashSet<_>>();
+    let (mut from_bigger, mut from_cut) = (HashSet::new(), HashSet::new());
     for sgc in subgraph_complements{
 
-        let subgraph = sgc.iter().collect::<HashSet<_>>();
-        let intersect_cut = sep.cut.iter().collect::<HashSet<_>>().intersection(&subgraph).count();
+        let in_cut = sgc.iter().filter(|v| cut.contains(v)).collect::<Vec<_>>();
 
-        if intersect_cut == sgc.len() - 1 {
-            d2 -= 1;
-        } else if intersect_cut == 1  {
-            d1 -= 1 ;
+        // A subgraph complement that acts as a vertex cut removes its one vertex
+        // on the other side of the cut from that sub instance. The complements lie
+        // between the cut and the bigger side, so that vertex is in `bigger`.
+        if in_cut.len() == sgc.len() - 1 {
+            from_bigger.extend(sgc.iter().filter(|v| !cut.contains(v)));
+        } else if in_cut.len() == 1  {
+            from_cut.extend(in_cut);
         }
     }
 
-    // a and b are the number of vertices removed
-    let (a, b) = (n - cmp::max(d1, d2), n- cmp::min(d1, d2));
-
+    // Each vertex is removed at most once, however many complements single it out.
+    d2 -= tcount(g, from_bigger);
+    d1 -= tcount(g, from_cut);
 
-    let f = |x: f32| {
-        a as f32 * x.ln() - (num_diag as f32) / 1.442695
-            + (1.0 + x.powi(a as i32 - b as i32)).ln_1p()
-        // the constant is lg(e)
-    };
+    // a and b are the number of non-Clifford spiders removed
+    let (a, b) = (n - cmp::max(d1, d2), n- cmp::min(d1, d2));
 
-    let t = find_root_brent(
-        1.0,
-        10.0,
-        f,
-        &mut SimpleConvergency {
-            eps: 0.001,
-            max_iter: 100,
-        },
-    )
-    .unwrap();
-    t.log2()
+    alpha(a, b, num_diag)
 }
 
-fn vertex_cut_in_subgraph_complement_cut((sep,sgcs):&(VertexSeparator<StableUnGraph<(), ()>>, Vec<Vec<NodeIndex>>)) -> usize{
+fn vertex_cut_in_subgraph_complement_cut<G: AnnealGraph>((sep,sgcs):&(VertexSeparator<G>, Vec<Vec<G::Vertex>>)) -> usize{
 
     let mut nb = 0;
     for sgc in sgcs{
@@ -342,40 +568,103 @@ fn vertex_cut_in_subgraph_complement_cut((sep,sgcs):&(VertexSeparator<StableUnGr
     nb
 }
 
-fn vertex_cut_alpha((sep,_):&(VertexSeparator<StableUnGraph<(), ()>>, Vec<Vec<NodeIndex>>),depth:isize) -> f32{
+fn vertex_cut_alpha<G: AnnealGraph>(g: &G, (sep,_):&(VertexSeparator<G>, Vec<Vec<G::Vertex>>),depth:isize) -> Result<f32, AlphaError>{
 
 
-    let n = (sep.left.len()+sep.right.len()+sep.cut.len()) as isize;
-    let (nb_d1, nb_d2) = (cmp::max(sep.right.len(), sep.left.len()), cmp::min(sep.right.len(), sep.left.len()));
+    let n = tcount(g, g.vertices());
+    let (left, right) = (tcount(g, &sep.left), tcount(g, &sep.right));
+    let (nb_d1, nb_d2) = (cmp::max(left, right), cmp::min(left, right));
 
-    // println!("nb_d1 : {} nb_d2 : {}, num_diag : {} ",nb_d1,nb_d2, sep.cut.len() as isize +depth);
-    let (a,b) = (n  - nb_d1 as isize, n- nb_d2 as isize);
-    // println!("a : {} b : {}, num_diag : {} ",a,b, sep.cut.len() as isize +depth);
-
-    let f = |x: f32| {
-        a as f32 * x.ln() - (sep.cut.len() as isize + depth) as f32 / 1.442695
-            + (1.0 + x.powi(a as i32 - b as i32)).ln_1p()
-        // the constant is lg(e)
-    };
-
-    let t = find_root_brent(
-        1.0,
-        10.0,
-        f,
-        &mut SimpleConvergency {
-            eps: 0.001,
-            max_iter: 100,
-        },
-    )
-    .unwrap();
-    t.log2()
+    let (a,b) = (n  - nb_d1, n- nb_d2);
+    let num_diag = (sep.cut.len() as isize + depth).max(0) as usize;
 
+    alpha(a, b, num_diag)
+}
 
+/// The number of non-Clifford spiders among `vertices`.
+fn tcount<G: AnnealGraph, I>(g: &G, vertices: I) -> usize
+where
+    I: IntoIterator,
+    I::Item: std::borrow::Borrow<G::Vertex>,
+{
+    vertices.into_iter().filter(|v| g.is_non_clifford(v.borrow().clone())).count()
 }
+
 fn log_barrier(x:usize,away_from:usize)->f32{
     if x >= away_from {
         2000.0 *(x as f32)/(away_from as f32)
     }else {
         -((away_from as f32 - x as f32)/away_from as f32).log2()
     }
-}
\ No newline at end of file
+}
+#[test]
+fn tied_separator_test() {
+    // On a tie `left` is the bigger side, which the complements are built against.
+    let mut g = StableUnGraph::<bool, ()>::default();
+    let (l, c, r) = (g.add_node(true), g.add_node(false), g.add_node(false));
+    g.add_edge(l, c, ());
+    g.add_edge(c, r, ());
+
+    let sep = VertexSeparator { left: vec![l], right: vec![r], cut: vec![c] };
+    let sgcs = CutState::new(&g, &sep).complement_cover();
+    assert!(sgcs.iter().flatten().all(|&v| v != r));
+    assert!(improved_alpha_subgraph_complements(&g, &sep, &sgcs, 0).is_ok());
+}
+
+#[test]
+fn zero_steps_test() {
+    use rand::{Rng, SeedableRng};
+
+    // Without any steps the run reports the toggled starting state it was built with.
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    let mut g = StableUnGraph::<bool, ()>::default();
+    let vs = (0..30).map(|_| g.add_node(true)).collect::<Vec<_>>();
+    for a in 0..30 {
+        for b in a + 1..30 {
+            if rng.gen::<f64>() < 0.2 {
+                g.add_edge(vs[a], vs[b], ());
+            }
+        }
+    }
+
+    let mut finder = ComplementFinder::new(&g, rng, GeometricSeries::new(1.0, 0.1, 0), 0, 350, 10);
+    let (graph, current, fitness) = (finder.graph.clone(), finder.current.clone(), finder.fitness);
+    let (sep, sgcs) = finder.current_cut.clone();
+    assert_eq!(current.len(), 3);
+
+    let report = finder.run(true);
+    assert_eq!(finder.current, current);
+    assert_eq!(finder.graph.edge_count(), graph.edge_count());
+    assert!(graph.edge_indices().all(|e| {
+        let (a, b) = graph.edge_endpoints(e).unwrap();
+        finder.graph.contains_edge(a, b)
+    }));
+    assert_eq!(report.best_fitness, fitness);
+    assert_eq!((report.left, report.cut, report.right), (sep.left.len(), sep.cut.len(), sep.right.len()));
+    assert_eq!(report.complements, sgcs.len());
+    assert!(report.trace.is_empty());
+}
+
+#[test]
+fn separator_seeds_test() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    let mut g = StableUnGraph::<bool, ()>::default();
+    let vs = (0..60).map(|_| g.add_node(true)).collect::<Vec<_>>();
+    for a in 0..60 {
+        for b in a + 1..60 {
+            if rng.gen::<f64>() < 0.1 {
+                g.add_edge(vs[a], vs[b], ());
+            }
+        }
+    }
+
+    // The kept separator has the smallest cut of the seeds tried.
+    let finder = ComplementFinder::new(&g, rng, GeometricSeries::new(1.0, 0.1, 0), 0, 350, 10).separator_seeds(8);
+    let options = metis::Options::default().max_imbalance(350);
+    let smallest = (1..=8)
+        .map(|s| metis::Graph::new(&finder.graph).vertex_separator(&options.clone().seed(s)).unwrap().cut.len())
+        .min();
+    assert_eq!(Some(finder.separator().cut.len()), smallest);
+}
@@ -2,8 +2,21 @@ use crate::rank::RankDecomposition;
 use metis::VertexSeparator;
 use petgraph as px;
 use px::stable_graph::{NodeIndex, StableUnGraph};
+use px::visit::{EdgeRef, NodeIndexable};
 use quizx::linalg::Mat2;
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// The smaller and bigger side of `sep` by number of vertices, with `left` taken
+/// as the bigger side on a tie. Subgraph complements are always built between the
+/// bigger side and the cut, so everything that picks a side must use this.
+pub fn sides<G: metis::GraphLike>(sep: &VertexSeparator<G>) -> (&Vec<G::Vertex>, &Vec<G::Vertex>) {
+    if sep.left.len() < sep.right.len() {
+        (&sep.left, &sep.right)
+    } else {
+        (&sep.right, &sep.left)
+    }
+}
 
 #[derive(Clone)]
 pub struct BiGraph<N, E> {
@@ -12,38 +25,74 @@ pub struct BiGraph<N, E> {
     pub right: Vec<px::graph::NodeIndex>,
 }
 
-impl<N: Clone, E: Clone> BiGraph<N, E> {
-    pub fn from_sep(g: &StableUnGraph<N, E>, sep: &VertexSeparator<StableUnGraph<N, E>>) -> Self {
-        let mut sep = sep.clone();
-        if sep.left.len() < sep.right.len() {
-            let temp = sep.right;
-            sep.right = sep.left;
-            sep.left = temp;
-        }
+/// Vertices that are small indices into their graph, as quizx vertices and petgraph
+/// node indices are, so that sets of them can be kept as masks.
+pub trait VertexIndex: Clone + Eq + Hash {
+    fn index(&self) -> usize;
+}
 
-        let mut subgraph = g.clone();
-        subgraph.retain_edges(|g, e| {
-            let (a, b) = g.edge_endpoints(e).unwrap();
-            (sep.left.contains(&a) && sep.cut.contains(&b))
-                || (sep.left.contains(&b) && sep.cut.contains(&a))
-        });
-        subgraph.retain_nodes(|g, n| g.neighbors(n).count() > 0);
-        let new_left = subgraph
-            .node_indices()
-            .filter(|n| sep.left.contains(n))
-            .collect::<Vec<_>>();
-        let new_right = subgraph
-            .node_indices()
-            .filter(|n| sep.cut.contains(n))
-            .collect::<Vec<_>>();
+impl VertexIndex for usize {
+    fn index(&self) -> usize {
+        *self
+    }
+}
 
-        BiGraph {
-            graph: subgraph,
-            left: new_left,
-            right: new_right,
+impl VertexIndex for NodeIndex {
+    fn index(&self) -> usize {
+        NodeIndex::index(*self)
+    }
+}
+
+impl<V: VertexIndex> BiGraph<V, ()> {
+    /// The edges between the bigger side of `sep` and its cut, in a bipartite graph
+    /// whose nodes each hold the vertex of `g` they stand for. Only the
+    /// neighbourhoods of the bigger side are visited.
+    pub fn from_separator<G>(g: &G, sep: &VertexSeparator<G>) -> Self
+    where
+        G: metis::GraphLike<Vertex = V>,
+    {
+        let (_, bigger) = sides(sep);
+        let cut = mask(sep.cut.iter().map(|v| v.index() + 1).max().unwrap_or(0), &sep.cut);
+
+        let mut graph = StableUnGraph::default();
+        let mut nodes = HashMap::new();
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+
+        for a in bigger {
+            for b in g.neighbors(a.clone()) {
+                if !cut.get(b.index()).copied().unwrap_or(false) {
+                    continue;
+                }
+
+                let na = *nodes.entry(a.clone()).or_insert_with(|| {
+                    let n = graph.add_node(a.clone());
+                    left.push(n);
+                    n
+                });
+                let nb = *nodes.entry(b.clone()).or_insert_with(|| {
+                    let n = graph.add_node(b.clone());
+                    right.push(n);
+                    n
+                });
+                graph.add_edge(na, nb, ());
+            }
         }
+
+        BiGraph { graph, left, right }
     }
+}
 
+/// Membership of `nodes` indexed by vertex index, for vertices with indices below `bound`.
+fn mask<V: VertexIndex>(bound: usize, nodes: &[V]) -> Vec<bool> {
+    let mut mask = vec![false; bound];
+    for n in nodes {
+        mask[n.index()] = true;
+    }
+    mask
+}
+
+impl<N: Clone, E: Clone> BiGraph<N, E> {
     pub fn min_vertex_cover(&self) -> Vec<px::graph::NodeIndex> {
         use rs_graph::{
             maxflow::MaxFlow, traits::GraphIterator, traits::GraphSize, traits::Undirected,
@@ -123,6 +172,35 @@ impl<N: Clone, E: Clone> BiGraph<N, E> {
         out.into_iter().map(|n| nodes_from[n]).collect::<Vec<_>>()
     }
 
+    /// Disconnect the two sides with one subgraph complement per vertex of a minimum
+    /// vertex cover, each holding the vertex and its neighbours across edges that no
+    /// earlier complement removed.
+    pub fn konig_cover(&self) -> Vec<Vec<NodeIndex>> {
+        if self.graph.edge_count() == 0 {
+            return Vec::new();
+        }
+
+        // The cover is collected from a hash set, so sort it to get the same
+        // complements on every run.
+        let mut cover = self.min_vertex_cover();
+        cover.sort();
+
+        let mut removed = HashSet::new();
+        cover
+            .into_iter()
+            .map(|v| {
+                let mut sgc = vec![v];
+                for e in self.graph.edges(v) {
+                    if removed.insert(e.id()) {
+                        sgc.push(e.target());
+                    }
+                }
+                sgc
+            })
+            .filter(|sgc| sgc.len() > 1)
+            .collect()
+    }
+
     pub fn biadjacency(&self) -> Mat2 {
         let mut mat = vec![vec![0; self.left.len()]; self.right.len()];
 
@@ -166,9 +244,10 @@ impl<N: Clone, E: Clone> BiGraph<N, E> {
 
         let right = right.to_vec();
 
+        let is_left = mask(n, &left);
         subgraph.retain_edges(|g, e| {
             let (a, b) = g.edge_endpoints(e).unwrap();
-            (left.contains(&a) && right.contains(&b)) || (left.contains(&b) && right.contains(&a))
+            is_left[a.index()] != is_left[b.index()]
         });
 
         BiGraph {
@@ -178,6 +257,19 @@ impl<N: Clone, E: Clone> BiGraph<N, E> {
         }
     }
 
+    /// One subgraph complement per vertex of `centres`, holding it and its
+    /// neighbours, which together act as a vertex cut of `centres`.
+    pub fn stars(&self, centres: &[NodeIndex]) -> Vec<Vec<NodeIndex>> {
+        centres
+            .iter()
+            .map(|&v| {
+                let mut sgc: Vec<NodeIndex> = self.graph.neighbors(v).collect();
+                sgc.push(v);
+                sgc
+            })
+            .collect()
+    }
+
     pub fn complement_cover(&self) -> Vec<Vec<NodeIndex>> {
         fn getcol(m: &Mat2, c: usize) -> Vec<u8> {
             let mut col = vec![0u8; m.num_rows()];
@@ -204,21 +296,9 @@ impl<N: Clone, E: Clone> BiGraph<N, E> {
 
         // if can be done by vertex cuts
         if rank == self.right.len()  {
-            for &v in &self.right{
-                let mut sgc: Vec<NodeIndex> = self.graph.neighbors(v).into_iter().collect();
-                sgc.push(v);
-                sgcs.push(sgc)
-            }
-            return sgcs;
-
+            return self.stars(&self.right);
         } else if rank == self.left.len() {
-            for &v in &self.left{
-                let mut sgc: Vec<NodeIndex> = self.graph.neighbors(v).into_iter().collect();
-                sgc.push(v);
-                sgcs.push(sgc)
-            }
-            return sgcs;
-            
+            return self.stars(&self.left);
         };
 
 
@@ -243,14 +323,41 @@ impl<N: Clone, E: Clone> BiGraph<N, E> {
         sgcs
     }
 
+    /// The number of edges between `left` and `right`, checked against masks of
+    /// the two sides rather than by scanning them.
     pub fn crossing_edges(&self) -> usize {
+        let left = mask(self.graph.node_bound(), &self.left);
+        let right = mask(self.graph.node_bound(), &self.right);
         self.graph
             .edge_indices()
             .filter(|&e| {
                 let (a, b) = self.graph.edge_endpoints(e).unwrap();
-                (self.left.contains(&a) && self.right.contains(&b))
-                    || (self.left.contains(&b) && self.right.contains(&a))
+                (left[a.index()] && right[b.index()]) || (left[b.index()] && right[a.index()])
             })
             .count()
     }
 }
+
+#[test]
+fn konig_cover_test() {
+    for _ in 0..50 {
+        let mut b = BiGraph::<(), ()>::random(30, 0.2, 0.4);
+        assert_eq!(b.crossing_edges(), b.graph.edge_count());
+        for sgc in b.konig_cover() {
+            for (i, &x) in sgc.iter().enumerate() {
+                for &y in &sgc[i + 1..] {
+                    match b.graph.find_edge(x, y) {
+                        Some(e) => {
+                            b.graph.remove_edge(e);
+                        }
+                        None => {
+                            b.graph.add_edge(x, y, ());
+                        }
+                    }
+                }
+            }
+        }
+
+        assert_eq!(b.crossing_edges(), 0);
+    }
+}
@@ -0,0 +1,155 @@
+//! Recursive decomposition of a scalar diagram into a binary tree of terms, each
+//! split in two by a subgraph complement, so that both terms are kept and the sum
+//! of the leaves equals the original diagram.
+
+use crate::utils_quizx::subgraph_complement;
+use quizx::graph::GraphLike;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// When to stop splitting a term.
+#[derive(Debug, Clone, Copy)]
+pub struct Target {
+    /// Terms with at most this many non-Clifford spiders are leaves.
+    pub tcount: usize,
+    /// Terms with at most this many spiders are leaves.
+    pub vertices: usize,
+    /// Terms this many splits below the root are leaves.
+    pub depth: usize,
+}
+
+/// A leaf diagram in the format read by `load_json`, with its scalar.
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagram {
+    phases: HashMap<usize, (isize, isize)>,
+    edges: Vec<(usize, usize)>,
+    scalar: (f64, f64),
+}
+
+impl Diagram {
+    fn new(g: &impl GraphLike) -> Self {
+        let scalar = g.scalar().float_value();
+        Diagram {
+            phases: g.vertices().map(|v| (v, g.phase(v).into())).collect(),
+            edges: g.edges().map(|(a, b, _)| (a, b)).collect(),
+            scalar: (scalar.re, scalar.im),
+        }
+    }
+}
+
+/// A node of the decomposition tree. Inner nodes have two children, one per term
+/// of `subgraph_complement` applied to `complement`; leaves keep their diagram.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "")]
+pub struct Term<G> {
+    /// The vertices complemented to split this term, empty for leaves.
+    pub complement: Vec<usize>,
+    /// The scalar of this term's diagram after simplification.
+    pub scalar: (f64, f64),
+    pub vertices: usize,
+    pub tcount: usize,
+    pub children: Vec<Term<G>>,
+    pub leaf: Option<Diagram>,
+    #[serde(skip)]
+    pub graph: Option<G>,
+}
+
+impl<G: GraphLike> Term<G> {
+    /// Split `g` recursively until `target` is reached. `find` returns the vertices
+    /// to complement in a graph, given how many splits are above it; a term for
+    /// which it returns fewer than two vertices becomes a leaf.
+    pub fn decompose(mut g: G, target: Target, find: &mut impl FnMut(&G, usize) -> Vec<usize>) -> Self {
+        quizx::simplify::full_simp(&mut g);
+        Self::split(g, 0, target, find)
+    }
+
+    fn split(g: G, depth: usize, target: Target, find: &mut impl FnMut(&G, usize) -> Vec<usize>) -> Self {
+        let scalar = g.scalar().float_value();
+        let mut term = Term {
+            complement: Vec::new(),
+            scalar: (scalar.re, scalar.im),
+            vertices: g.num_vertices(),
+            tcount: g.vertices().filter(|&v| *g.phase(v).denom() > 2).count(),
+            children: Vec::new(),
+            leaf: None,
+            graph: None,
+        };
+
+        let complement = if term.tcount <= target.tcount || term.vertices <= target.vertices || depth >= target.depth {
+            Vec::new()
+        } else {
+            find(&g, depth)
+        };
+
+        if complement.len() < 2 {
+            term.leaf = Some(Diagram::new(&g));
+            term.graph = Some(g);
+            return term;
+        }
+
+        let (mut a, mut b) = subgraph_complement(&g, &complement);
+        quizx::simplify::full_simp(&mut a);
+        quizx::simplify::full_simp(&mut b);
+        term.children = vec![
+            Self::split(a, depth + 1, target, find),
+            Self::split(b, depth + 1, target, find),
+        ];
+        term.complement = complement;
+        term
+    }
+
+    pub fn leaves(&self) -> usize {
+        if self.children.is_empty() {
+            1
+        } else {
+            self.children.iter().map(Term::leaves).sum()
+        }
+    }
+
+    /// The value of the diagram this term stands for, as the sum of `leaf` over
+    /// the diagrams of its leaves.
+    pub fn evaluate<S: std::iter::Sum>(&self, leaf: &mut impl FnMut(&G) -> S) -> S {
+        match &self.graph {
+            Some(g) => leaf(g),
+            None => self.children.iter().map(|t| t.evaluate(leaf)).sum(),
+        }
+    }
+}
+
+/// A random diagram of `n` Z spiders with phases that are multiples of π/4, each
+/// pair joined by a Hadamard edge with probability 1/2, for tests.
+#[cfg(test)]
+pub fn random_graph(rng: &mut impl rand::Rng, n: usize) -> quizx::vec_graph::Graph {
+    use quizx::vec_graph::{EType, Graph, VType};
+
+    let mut g = Graph::new();
+    for _ in 0..n {
+        g.add_vertex_with_phase(VType::Z, num::Rational::new(rng.gen_range(0..8), 4));
+    }
+    for a in 0..n {
+        for b in a + 1..n {
+            if rng.gen::<f32>() < 0.5 {
+                g.add_edge_with_type(a, b, EType::H);
+            }
+        }
+    }
+    g
+}
+
+#[test]
+fn evaluate_test() {
+    use quizx::tensor::ToTensor;
+    use rand::Rng;
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..20 {
+        let n = rng.gen_range(6..10);
+        let g = random_graph(&mut rng, n);
+
+        let expected = g.to_tensor4().iter().next().unwrap().float_value();
+        let target = Target { tcount: 0, vertices: 0, depth: 2 };
+        let tree = Term::decompose(g, target, &mut |g, _| g.vertices().take(3).collect());
+        let value = tree.evaluate(&mut |g| g.to_tensor4().iter().next().unwrap().float_value());
+        assert!((value - expected).norm() < 1e-9, "leaves sum to {} instead of {}", value, expected);
+    }
+}
@@ -0,0 +1,381 @@
+use crate::bigraph::{sides, BiGraph, VertexIndex};
+use crate::rank::RankDecomposition;
+use metis::{GraphLike, VertexSeparator};
+use petgraph::stable_graph::NodeIndex;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    /// A vertex of the bigger side, by its column in the biadjacency matrix.
+    Bigger(usize),
+    Smaller,
+    /// A vertex of the cut, by its row in the biadjacency matrix.
+    Cut(usize),
+}
+
+/// What a batch of edge toggles did to a `CutState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// An edge now joins the two sides, so the separator must be recomputed. The
+    /// state is left as it was.
+    Invalid,
+    /// The bipartite graph is unchanged, and so is its complement cover.
+    Unchanged,
+    /// The bipartite graph changed, so its complement cover must be recomputed.
+    Changed,
+}
+
+/// A vector over GF(2).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn zeros(len: usize) -> Self {
+        Bits(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, i: usize) -> bool {
+        (self.0[i / 64] >> (i % 64)) & 1 == 1
+    }
+
+    fn flip(&mut self, i: usize) {
+        self.0[i / 64] ^= 1 << (i % 64);
+    }
+
+    fn xor(&mut self, other: &Bits) {
+        for (a, b) in self.0.iter_mut().zip(&other.0) {
+            *a ^= b;
+        }
+    }
+
+    fn first_one(&self) -> Option<usize> {
+        self.0.iter()
+            .position(|&w| w != 0)
+            .map(|i| i * 64 + self.0[i].trailing_zeros() as usize)
+    }
+
+    fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &w)| {
+            (0..64).filter(move |b| (w >> b) & 1 == 1).map(move |b| i * 64 + b)
+        })
+    }
+}
+
+/// A vector that is the sum of some of the others, as its index and theirs.
+fn dependency<'a>(vectors: impl Iterator<Item = &'a Bits>) -> Option<(usize, Vec<usize>)> {
+    // Reduced vectors with their pivot and the inputs that sum to them.
+    let mut basis: Vec<(Bits, usize, Vec<usize>)> = Vec::new();
+    for (j, v) in vectors.enumerate() {
+        let mut v = v.clone();
+        let mut sum = vec![j];
+        for (b, pivot, from) in &basis {
+            if v.get(*pivot) {
+                v.xor(b);
+                for &i in from {
+                    match sum.iter().position(|&k| k == i) {
+                        Some(k) => {
+                            sum.swap_remove(k);
+                        }
+                        None => sum.push(i),
+                    }
+                }
+            }
+        }
+
+        match v.first_one() {
+            Some(pivot) => basis.push((v, pivot, sum)),
+            None => return Some((j, sum.into_iter().filter(|&i| i != j).collect())),
+        }
+    }
+
+    None
+}
+
+/// The biadjacency matrix between the cut (rows) and the bigger side (columns) as
+/// a sum of rank-one terms `col · rowᵀ`. The columns and the rows are each kept
+/// linearly independent, so the number of terms is the rank of the matrix.
+#[derive(Debug, Clone)]
+struct Factors {
+    terms: Vec<(Bits, Bits)>,
+}
+
+impl Factors {
+    /// Add `col · rowᵀ` to the matrix.
+    fn add(&mut self, col: Bits, row: Bits) {
+        self.terms.push((col, row));
+
+        // Removing a dependency on one side can expose another on the other side,
+        // so repeat until there is none.
+        loop {
+            if let Some((j, others)) = dependency(self.terms.iter().map(|t| &t.0)) {
+                // col_j = Σ col_i, so col_j · row_jᵀ is spread over those terms.
+                let row = self.terms.remove(j).1;
+                for i in others {
+                    self.terms[if i > j { i - 1 } else { i }].1.xor(&row);
+                }
+            } else if let Some((j, others)) = dependency(self.terms.iter().map(|t| &t.1)) {
+                let col = self.terms.remove(j).0;
+                for i in others {
+                    self.terms[if i > j { i - 1 } else { i }].0.xor(&col);
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn rank(&self) -> usize {
+        self.terms.len()
+    }
+}
+
+/// The bipartite graph between the bigger side of a separator and its cut, kept
+/// up to date as edges are toggled so that the separator is only recomputed when
+/// an edge crosses it. The rank factorisation of its biadjacency matrix is updated
+/// with rank-one updates, so the complement cover needs no fresh decomposition.
+#[derive(Clone)]
+pub struct CutState<V> {
+    side: HashMap<V, Side>,
+    bigger: Vec<V>,
+    cut: Vec<V>,
+    bigraph: BiGraph<V, ()>,
+    nodes: HashMap<V, NodeIndex>,
+    factors: Factors,
+}
+
+impl<V: VertexIndex> CutState<V> {
+    pub fn new<G: GraphLike<Vertex = V>>(g: &G, sep: &VertexSeparator<G>) -> Self {
+        let (smaller, bigger) = sides(sep);
+
+        let side = bigger.iter().enumerate().map(|(j, v)| (v.clone(), Side::Bigger(j)))
+            .chain(smaller.iter().map(|v| (v.clone(), Side::Smaller)))
+            .chain(sep.cut.iter().enumerate().map(|(i, v)| (v.clone(), Side::Cut(i))))
+            .collect::<HashMap<_, _>>();
+        let bigraph = BiGraph::from_separator(g, sep);
+        let nodes = bigraph.graph
+            .node_indices()
+            .map(|n| (bigraph.graph[n].clone(), n))
+            .collect();
+
+        // The decomposition only covers the vertices in the bipartite graph.
+        let (c, r) = bigraph.biadjacency().rank_decomposition();
+        let terms = (0..c.num_cols())
+            .map(|k| {
+                let mut col = Bits::zeros(sep.cut.len());
+                for (row, n) in bigraph.right.iter().enumerate() {
+                    if let (1, Side::Cut(i)) = (c[(row, k)], side[&bigraph.graph[*n]]) {
+                        col.flip(i);
+                    }
+                }
+                let mut row = Bits::zeros(bigger.len());
+                for (column, n) in bigraph.left.iter().enumerate() {
+                    if let (1, Side::Bigger(j)) = (r[(k, column)], side[&bigraph.graph[*n]]) {
+                        row.flip(j);
+                    }
+                }
+                (col, row)
+            })
+            .collect();
+
+        CutState {
+            side,
+            bigger: bigger.clone(),
+            cut: sep.cut.clone(),
+            bigraph,
+            nodes,
+            factors: Factors { terms },
+        }
+    }
+
+    /// Record that the edges between each pair in `toggled` were added or removed.
+    /// Recording the same pairs again undoes the update.
+    pub fn update(&mut self, toggled: &[(V, V)]) -> Update {
+        let mut flips = Vec::new();
+        for (a, b) in toggled {
+            match (self.side[a], self.side[b]) {
+                // The separator was valid, so this edge has just been added.
+                (Side::Bigger(_), Side::Smaller) | (Side::Smaller, Side::Bigger(_)) => return Update::Invalid,
+                (Side::Bigger(j), Side::Cut(i)) | (Side::Cut(i), Side::Bigger(j)) => flips.push((i, j)),
+                _ => {}
+            }
+        }
+
+        if flips.is_empty() {
+            return Update::Unchanged;
+        }
+
+        for &(i, j) in &flips {
+            self.toggle(self.bigger[j].clone(), self.cut[i].clone());
+        }
+        self.flip_factors(&flips);
+
+        Update::Changed
+    }
+
+    /// Flip entries of the biadjacency matrix with one rank-one update per row or
+    /// per column, whichever takes fewer. Toggling a vertex flips entries in a
+    /// single row or column, so that is one update.
+    fn flip_factors(&mut self, flips: &[(usize, usize)]) {
+        let mut rows: HashMap<usize, Bits> = HashMap::new();
+        let mut cols: HashMap<usize, Bits> = HashMap::new();
+        for &(i, j) in flips {
+            rows.entry(i).or_insert_with(|| Bits::zeros(self.bigger.len())).flip(j);
+            cols.entry(j).or_insert_with(|| Bits::zeros(self.cut.len())).flip(i);
+        }
+
+        if rows.len() <= cols.len() {
+            for (i, row) in rows {
+                let mut col = Bits::zeros(self.cut.len());
+                col.flip(i);
+                self.factors.add(col, row);
+            }
+        } else {
+            for (j, col) in cols {
+                let mut row = Bits::zeros(self.bigger.len());
+                row.flip(j);
+                self.factors.add(col, row);
+            }
+        }
+    }
+
+    /// The subgraph complements from the rank factorisation of the biadjacency
+    /// matrix, or a vertex cut when the rank equals the size of either side.
+    pub fn complement_cover(&self) -> Vec<Vec<V>> {
+        let rank = self.factors.rank();
+        if rank == self.bigraph.right.len() {
+            return self.vertices(self.bigraph.stars(&self.bigraph.right));
+        } else if rank == self.bigraph.left.len() {
+            return self.vertices(self.bigraph.stars(&self.bigraph.left));
+        }
+
+        self.factors.terms
+            .iter()
+            .map(|(col, row)| {
+                col.ones().map(|i| self.cut[i].clone())
+                    .chain(row.ones().map(|j| self.bigger[j].clone()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The subgraph complements from a minimum vertex cover of the bipartite graph.
+    pub fn konig_cover(&self) -> Vec<Vec<V>> {
+        self.vertices(self.bigraph.konig_cover())
+    }
+
+    fn vertices(&self, sgcs: Vec<Vec<NodeIndex>>) -> Vec<Vec<V>> {
+        sgcs.into_iter()
+            .map(|sgc| sgc.into_iter().map(|n| self.bigraph.graph[n].clone()).collect())
+            .collect()
+    }
+
+    fn toggle(&mut self, a: V, b: V) {
+        let na = self.node(a, true);
+        let nb = self.node(b, false);
+
+        if let Some(e) = self.bigraph.graph.find_edge(na, nb) {
+            self.bigraph.graph.remove_edge(e);
+            self.remove_if_isolated(na);
+            self.remove_if_isolated(nb);
+        } else {
+            self.bigraph.graph.add_edge(na, nb, ());
+        }
+    }
+
+    fn node(&mut self, v: V, left: bool) -> NodeIndex {
+        if let Some(&n) = self.nodes.get(&v) {
+            return n;
+        }
+
+        let n = self.bigraph.graph.add_node(v.clone());
+        if left {
+            self.bigraph.left.push(n);
+        } else {
+            self.bigraph.right.push(n);
+        }
+        self.nodes.insert(v, n);
+        n
+    }
+
+    /// The bipartite graph only holds vertices with an edge across the cut.
+    fn remove_if_isolated(&mut self, n: NodeIndex) {
+        if self.bigraph.graph.neighbors(n).next().is_none() {
+            let v = self.bigraph.graph.remove_node(n).unwrap();
+            self.nodes.remove(&v);
+            self.bigraph.left.retain(|&m| m != n);
+            self.bigraph.right.retain(|&m| m != n);
+        }
+    }
+}
+
+#[test]
+fn incremental_update_test() {
+    use petgraph::stable_graph::StableUnGraph;
+    use rand::{Rng, SeedableRng};
+
+    fn toggle(g: &mut StableUnGraph<bool, ()>, a: NodeIndex, b: NodeIndex) {
+        match g.find_edge(a, b) {
+            Some(e) => { g.remove_edge(e); }
+            None => { g.add_edge(a, b, ()); }
+        }
+    }
+
+    fn edges(state: &CutState<NodeIndex>) -> Vec<(NodeIndex, NodeIndex)> {
+        let graph = &state.bigraph.graph;
+        let mut edges = graph
+            .edge_indices()
+            .map(|e| {
+                let (a, b) = graph.edge_endpoints(e).unwrap();
+                (graph[a].min(graph[b]), graph[a].max(graph[b]))
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        edges
+    }
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    for _ in 0..20 {
+        let mut g = StableUnGraph::<bool, ()>::default();
+        let vs = (0..40).map(|_| g.add_node(false)).collect::<Vec<_>>();
+        let sep = VertexSeparator { left: vs[..18].to_vec(), cut: vs[18..26].to_vec(), right: vs[26..].to_vec() };
+        let crosses = |a: usize, b: usize| (a < 18 && b >= 26) || (b < 18 && a >= 26);
+        for a in 0..40 {
+            for b in a + 1..40 {
+                if !crosses(a, b) && rng.gen::<f64>() < 0.15 {
+                    g.add_edge(vs[a], vs[b], ());
+                }
+            }
+        }
+
+        let mut state = CutState::new(&g, &sep);
+        for _ in 0..50 {
+            // Toggle the edges from one vertex to a few others, as annealing does.
+            let v = rng.gen_range(0..40);
+            let others = (0..40).filter(|&u| u != v && rng.gen::<f64>() < 0.1).collect::<Vec<_>>();
+            let toggled = others.iter().map(|&u| (vs[v], vs[u])).collect::<Vec<_>>();
+            for &(a, b) in &toggled {
+                toggle(&mut g, a, b);
+            }
+
+            if state.update(&toggled) == Update::Invalid {
+                assert!(others.iter().any(|&u| crosses(v, u)));
+                for &(a, b) in &toggled {
+                    toggle(&mut g, a, b);
+                }
+                continue;
+            }
+
+            let fresh = CutState::new(&g, &sep);
+            assert_eq!(edges(&state), edges(&fresh));
+            assert_eq!(state.bigraph.left.len(), fresh.bigraph.left.len());
+            assert_eq!(state.bigraph.right.len(), fresh.bigraph.right.len());
+            assert_eq!(state.factors.rank(), fresh.factors.rank());
+            for (i, &c) in state.cut.iter().enumerate() {
+                for (j, &b) in state.bigger.iter().enumerate() {
+                    let product = state.factors.terms.iter().filter(|(col, row)| col.get(i) && row.get(j)).count() % 2;
+                    assert_eq!(product == 1, g.find_edge(c, b).is_some());
+                }
+            }
+        }
+    }
+}
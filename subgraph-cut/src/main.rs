@@ -1,17 +1,32 @@
 #![allow(dead_code)]
 
-use crate::{anneal::{ComplementFinder, GeometricSeries}, utils_quizx::{subgraph_complement, indices_petgraph_to_quizx}};
+use crate::{
+    anneal::{ComplementFinder, CoverStrategy, GeometricSeries, RunReport},
+    decompose::{Target, Term},
+    restart::best_restart,
+    utils_quizx::subgraph_complement,
+};
+use clap::Parser;
 use petgraph as px;
 use px::stable_graph::StableUnGraph;
 use quizx::{
     circuit::Circuit,
     hash_graph::GraphLike,
+    tensor::ToTensor,
+    graph::{EType, VType},
+    scalar::ScalarN,
     vec_graph::{BasisElem, Graph},
 };
-use std::{collections::HashMap, vec};
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::{collections::HashMap, fs::File, path::{Path, PathBuf}, vec};
 
+mod alpha;
 mod anneal;
 mod bigraph;
+mod decompose;
+mod incremental;
+mod restart;
 mod utils_quizx;
 mod rank;
 
@@ -24,22 +39,19 @@ trait GraphUtils {
 }
 
 impl<G: quizx::graph::GraphLike> GraphUtils for G {
-    type Node = ();
+    /// Whether the spider is non-Clifford.
+    type Node = bool;
     type Edge = ();
 
     fn to_petgraph(&self) -> StableUnGraph<Self::Node, Self::Edge> {
+        let mut graph = StableUnGraph::default();
         let vmapping = self
             .vertices()
-            .scan(0, |i, s| {
-                *i += 1;
-                Some((s, *i - 1))
-            })
+            .map(|v| (v, graph.add_node(*self.phase(v).denom() > 2)))
             .collect::<HashMap<_, _>>();
-        let edges = self
-            .edges()
-            .map(|x| (vmapping[&x.0], vmapping[&x.1]))
-            .collect::<Vec<_>>();
-        let graph = px::stable_graph::StableUnGraph::<(), ()>::from_edges(&edges);
+        for (a, b, _) in self.edges() {
+            graph.add_edge(vmapping[&a], vmapping[&b], ());
+        }
         graph
     }
 
@@ -72,98 +84,199 @@ impl<G: quizx::graph::GraphLike> GraphUtils for G {
     }
 }
 
-fn main() {
-    let c = Circuit::random()
-        .qubits(60)
-        .depth(2500)
-        .seed(3513513)
-        .clifford_t(0.1)
-        .build();
-
-    let mut g: Graph = c.clone().to_graph();
+#[derive(Parser)]
+struct Args {
+    #[clap(help = "Input circuit (.qasm) or graph (.json), a random Clifford+T circuit is used if omitted")]
+    input: Option<String>,
+    #[clap(short, long, default_value_t = 5, help = "Number of rounds of subgraph complements")]
+    rounds: usize,
+    #[clap(short, long, default_value_t = 1, help = "Number of subgraph complements already applied before the first round")]
+    depth: usize,
+    #[clap(short, long, default_value_t = 350, help = "Maximum imbalance of the vertex separator, in thousandths")]
+    imbalance: usize,
+    #[clap(short = 'c', long, default_value_t = 1000, help = "Soft limit on the number of subgraph complements in a cut")]
+    max_nb_complement: usize,
+    #[clap(short, long, default_value_t = 10000, help = "Number of annealing steps")]
+    steps: usize,
+    #[clap(short = 'M', long, default_value_t = 0.1, help = "Starting temperature for annealing")]
+    max_temp: f32,
+    #[clap(short = 'm', long, default_value_t = 0.001, help = "End temperature for annealing")]
+    min_temp: f32,
+    #[clap(long, default_value_t = 1, help = "Number of independent annealing chains per round, the best is kept")]
+    restarts: usize,
+    #[clap(long, default_value_t = 0, help = "Number of threads for the chains, one per core if 0")]
+    threads: usize,
+    #[clap(long, help = "Seed for annealing, a random seed is used if omitted")]
+    seed: Option<u64>,
+    #[clap(short, long, help = "Don't simplify the diagram after each round")]
+    no_simp: bool,
+    #[clap(short, long, help = "Only print the summary of each round")]
+    quiet: bool,
+    #[clap(long, help = "Recompute the vertex separator at every annealing step")]
+    full_fitness: bool,
+    #[clap(long, default_value_t = 1, help = "Number of METIS seeds tried for each vertex separator, the smallest cut is kept")]
+    separator_seeds: usize,
+    #[clap(long, value_enum, default_value_t = CoverStrategy::Rank, help = "How the edges across each separator are covered by subgraph complements")]
+    cover: CoverStrategy,
+    #[clap(long, help = "Write the report of every round to this JSON file")]
+    report: Option<PathBuf>,
+    #[clap(long, help = "Keep both terms of every complement and write the tree of terms to this JSON file, with at most `rounds` levels")]
+    tree: Option<PathBuf>,
+    #[clap(long, default_value_t = 10, help = "Terms of the tree with at most this T-count are not split further")]
+    target_tcount: usize,
+    #[clap(long, default_value_t = 0, help = "Terms of the tree with at most this many spiders are not split further")]
+    target_vertices: usize,
+    #[clap(long, help = "Evaluate the tree by contracting the tensor of each leaf")]
+    evaluate: bool,
+    #[clap(long, default_value_t = 60, help = "Number of qubits of the random circuit")]
+    qubits: usize,
+    #[clap(long, default_value_t = 2500, help = "Depth of the random circuit")]
+    circuit_depth: usize,
+    #[clap(long, default_value_t = 0.1, help = "Fraction of gates that are T-gates in the random circuit")]
+    fraction_t: f32,
+    #[clap(long, default_value_t = 3513513, help = "Seed for the random circuit")]
+    circuit_seed: u64
+}
+
+/// A graph as written by `sparsify`: the phase of each spider, the Hadamard edges
+/// between them and the scalar, as its real and imaginary parts.
+#[derive(Deserialize)]
+struct JsonGraph {
+    phases: HashMap<usize, (isize, isize)>,
+    edges: Vec<(usize, usize)>,
+    scalar: (f64, f64),
+}
+
+fn load_json(path: &Path) -> Graph {
+    let json: JsonGraph = serde_json::from_reader(File::open(path).unwrap()).unwrap();
+
+    let mut ids = json.phases.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut g = Graph::new();
+    // The decomposition keeps the scalar of every term, so it must start from the
+    // stored one.
+    let (re, im) = json.scalar;
+    *g.scalar_mut() = ScalarN::Float(num::complex::Complex::new(re, im));
+
+    let vertices = ids
+        .into_iter()
+        .map(|i| (i, g.add_vertex_with_phase(VType::Z, json.phases[&i].into())))
+        .collect::<HashMap<_, _>>();
+    for (a, b) in json.edges {
+        g.add_edge_smart(vertices[&a], vertices[&b], EType::H);
+    }
+
+    g
+}
+
+fn circuit_graph(c: &Circuit) -> Graph {
+    let mut g: Graph = c.to_graph();
     g.plug_outputs(&vec![BasisElem::Z0; c.num_qubits()]);
     g.plug_inputs(&vec![BasisElem::Z0; c.num_qubits()]);
+    g
+}
+
+fn load_graph(args: &Args) -> Graph {
+    let mut g = match &args.input {
+        Some(path) if Path::new(path).extension().is_some_and(|e| e == "json") => load_json(Path::new(path)),
+        Some(path) => circuit_graph(&Circuit::from_file(path).unwrap()),
+        None => circuit_graph(
+            &Circuit::random()
+                .qubits(args.qubits)
+                .depth(args.circuit_depth)
+                .seed(args.circuit_seed)
+                .clifford_t(args.fraction_t)
+                .build()
+        )
+    };
 
     quizx::simplify::full_simp(&mut g);
+    g
+}
 
-    println!("{:?}", g.num_vertices());
-    let mut rng = rand::thread_rng();
-
-
-    // different depth
-
-    // let mut gp = g.clone().to_petgraph();
-    // for i in 1..=5 {
-    //     let mut finder = ComplementFinder::new(
-    //         &gp,
-    //         &mut rng,
-    //         GeometricSeries::new(0.1, 0.001, 10000),
-    //         i,
-    //         350,
-    //         40,
-    //     );
-    //     finder.run(false);
-
-    //     gp = finder.graph;
-    //     println!("--------------------------")
-    // }
-
-    let mut zxg = g.clone();
-    let initial_vertices: Vec<_> = zxg.vertices().collect();
-
-    println!("{:?}",initial_vertices);
-
-    for i in 1..=5 {
-        let mut finder = ComplementFinder::new(
-            &zxg.clone().to_petgraph(),
-            &mut rng,
-            GeometricSeries::new(0.1, 0.001, 10000),
-            i,
-            350,
-            1000,
-        );
-        finder.run(false);
-        let subgraph = finder.solution_found().into_iter().map(|x| x.index()).collect();
-        let subgraph = indices_petgraph_to_quizx(&zxg, &subgraph);
-        println!("size of the complement {}",subgraph.len());
-        zxg = subgraph_complement(&zxg, &subgraph).0;
-        println!("--------------------------")
+/// Anneal for the best complement of `g`, which already has `depth` complements
+/// applied, with `args.restarts` chains.
+fn find_complement(g: &Graph, depth: usize, args: &Args, rng: &mut impl Rng) -> (RunReport, Vec<usize>) {
+    let seeds = (0..args.restarts.max(1)).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let best = best_restart(&seeds, args.threads, args.quiet, |rng| {
+        ComplementFinder::new(
+            g,
+            rng,
+            GeometricSeries::new(args.max_temp, args.min_temp, args.steps),
+            depth,
+            args.imbalance,
+            args.max_nb_complement,
+        )
+        .incremental(!args.full_fitness)
+        .cover_strategy(args.cover)
+        .separator_seeds(args.separator_seeds)
+    })
+    .unwrap();
+
+    println!(
+        "alpha = {:?}, complements = {}, time = {:.2}s, chain seed = {}",
+        best.report.alpha, best.report.complements, best.report.seconds, best.seed
+    );
+    (best.report, best.solution.into_iter().collect())
+}
+
+fn decompose(zxg: Graph, args: &Args, rng: &mut impl Rng, reports: &mut Vec<RunReport>) {
+    let target = Target {
+        tcount: args.target_tcount,
+        vertices: args.target_vertices,
+        depth: args.rounds,
+    };
+    let tree = Term::decompose(zxg, target, &mut |g, level| {
+        println!("splitting a term of {} vertices at level {}", g.num_vertices(), level);
+        let (report, subgraph) = find_complement(g, args.depth + level, args, rng);
+        reports.push(report);
+        subgraph
+    });
+    println!("{} leaves", tree.leaves());
+
+    if args.evaluate {
+        let value = tree.evaluate(&mut |g| {
+            g.to_tensor4().iter().map(|s| s.float_value()).next().unwrap()
+        });
+        println!("value = {}", value);
     }
-    println!("with simp");
-    for i in 1..=5 {
-        println!("{} sommets restants",zxg.num_vertices());
-        let mut finder = ComplementFinder::new(
-            &zxg.clone().to_petgraph(),
-            &mut rng,
-            GeometricSeries::new(0.1, 0.001, 10000),
-            i,
-            350,
-            1000,
-        );
-        finder.run(false);
-        let subgraph = finder.solution_found().into_iter().map(|x| x.index()).collect();
-        let subgraph = indices_petgraph_to_quizx(&zxg, &subgraph);
-        println!("{:?}",subgraph);
-        zxg = subgraph_complement(&zxg, &subgraph).0;
-        quizx::simplify::full_simp(&mut zxg);
-        println!("--------------------------")
+
+    if let Some(path) = &args.tree {
+        serde_json::to_writer(File::create(path).unwrap(), &tree).unwrap();
+        println!("wrote: `{}`", path.display());
     }
+}
+
+fn main() {
+    let args = Args::parse();
 
+    let seed = args.seed.unwrap_or(rand::thread_rng().gen::<u64>());
+    println!("seed = {}", seed);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
 
+    let mut zxg = load_graph(&args);
+    println!("vertices = {} edges = {}", zxg.num_vertices(), zxg.num_edges());
 
-    //different inbalence 
-    // for i in 1..=10 {
-    //     let imbalance = i*50;
-    //     println!("Imbalance = {}", imbalance);
-    //     let mut finder = ComplementFinder::new(
-    //         &g.clone().to_petgraph(),
-    //         &mut rng,
-    //         GeometricSeries::new(0.15, 0.002, 10000),
-    //         1,
-    //         imbalance,
-    //         0,
-    //     );
-    //     finder.run(false);
-    //     println!("--------------------------")
-    // }
+    let mut reports = Vec::new();
+    if args.tree.is_some() || args.evaluate {
+        decompose(zxg, &args, &mut rng, &mut reports);
+    } else {
+        for i in 0..args.rounds {
+            let (report, subgraph) = find_complement(&zxg, args.depth + i, &args, &mut rng);
+            reports.push(report);
+            println!("size of the complement {}", subgraph.len());
+            zxg = subgraph_complement(&zxg, &subgraph).0;
+            if !args.no_simp {
+                quizx::simplify::full_simp(&mut zxg);
+            }
+            println!("{} vertices remaining", zxg.num_vertices());
+            println!("--------------------------")
+        }
+    }
+
+    if let Some(path) = &args.report {
+        serde_json::to_writer(File::create(path).unwrap(), &reports).unwrap();
+        println!("wrote: `{}`", path.display());
+    }
 }
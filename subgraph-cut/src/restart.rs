@@ -0,0 +1,66 @@
+//! Running several independent annealing chains on a pool of threads and keeping
+//! the best one, since a single chain often settles in a poor cut.
+
+use crate::anneal::{AnnealGraph, ComplementFinder, RunReport};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The result of one chain of `best_restart`.
+pub struct Restart<V> {
+    pub seed: u64,
+    pub solution: HashSet<V>,
+    pub report: RunReport,
+}
+
+/// Run one chain per seed, each built by `finder` from a generator seeded with it,
+/// using up to `threads` threads, or one per available core if `threads` is zero.
+/// Returns the restart with the lowest best fitness, preferring earlier seeds on
+/// ties, or `None` if `seeds` is empty. The chains' calls into METIS are serialised
+/// by the metis crate, so each chain is reproducible from its seed.
+pub fn best_restart<G, T>(
+    seeds: &[u64],
+    threads: usize,
+    quiet: bool,
+    finder: impl Fn(StdRng) -> ComplementFinder<G, StdRng, T> + Sync,
+) -> Option<Restart<G::Vertex>>
+where
+    G: AnnealGraph,
+    G::Vertex: Send,
+    T: Iterator<Item = f32>,
+{
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(seeds.len());
+
+    // Progress of concurrent chains would be interleaved, so only a lone chain prints it.
+    let quiet = quiet || seeds.len() > 1;
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..seeds.len()).map(|_| None).collect::<Vec<_>>());
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(&seed) = seeds.get(i) else { break };
+                let mut chain = finder(StdRng::seed_from_u64(seed));
+                let report = chain.run(quiet);
+                let solution = chain.solution_found();
+                results.lock().unwrap()[i] = Some(Restart { seed, solution, report });
+            });
+        }
+    });
+
+    let mut best: Option<Restart<G::Vertex>> = None;
+    for restart in results.into_inner().unwrap().into_iter().map(Option::unwrap) {
+        if best.as_ref().is_none_or(|b| restart.report.best_fitness < b.report.best_fitness) {
+            best = Some(restart);
+        }
+    }
+
+    best
+}
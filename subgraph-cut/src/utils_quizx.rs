@@ -1,22 +1,30 @@
-use std::collections::HashMap;
-
 use quizx::vec_graph::{GraphLike, EType};
 use num::{self, Rational};
 
 
-// This does not work out the correct scalar factors!!!
+/// Complement the edges between `vertices` and split the result into two terms,
+/// with phases +π/2 and -π/2 added to each vertex, whose sum equals `g`.
+///
+/// For k of the vertices set to 1, complementing contributes a sign (-1)^(k choose 2),
+/// which is e^(-iπ/4)/√2 · i^k + e^(iπ/4)/√2 · (-i)^k. Each complemented edge is
+/// added with `add_edge_smart`, which keeps the diagram equal to `g` with an extra
+/// Hadamard edge (and factor 1/√2) per pair, so that factor is undone as well.
 pub fn subgraph_complement<G: GraphLike>(g : & G, vertices : & Vec<usize>) -> (G,G){
 
     let mut g =g.clone();
-    
-    for &v1 in vertices {
-        for &v2 in vertices  {
-            if v1==v2 {continue;}
+
+    for (i, &v1) in vertices.iter().enumerate() {
+        for &v2 in &vertices[i + 1..] {
             g.add_edge_smart(v1, v2, EType::H);
         }
     }
 
+    let pairs = vertices.len() * vertices.len().saturating_sub(1) / 2;
+    g.scalar_mut().mul_rt2_pow(pairs as i32 - 1);
+
     let mut g2 = g.clone();
+    g.scalar_mut().mul_phase(Rational::new(-1, 4));
+    g2.scalar_mut().mul_phase(Rational::new(1, 4));
 
     for &v in vertices{
         g.add_to_phase(v, Rational::new(1,2));
@@ -26,11 +34,26 @@ pub fn subgraph_complement<G: GraphLike>(g : & G, vertices : & Vec<usize>) -> (G
     (g,g2)
 }
 
-pub fn indices_petgraph_to_quizx<G: GraphLike>(g : & G,indices: & Vec<usize>) -> Vec<usize>{
-
-    let vmapping = g.vertices().scan(0, |i, s| { *i += 1; Some((*i - 1, s)) }).collect::<HashMap<_,_>>();
-
-    indices.iter().map(|x| vmapping[x]).collect()
-
-
-}
\ No newline at end of file
+#[test]
+fn subgraph_complement_test() {
+    use crate::decompose::random_graph;
+    use quizx::tensor::ToTensor;
+    use rand::{seq::index::sample, Rng};
+
+    let mut rng = rand::thread_rng();
+    for _ in 0..100 {
+        let n = rng.gen_range(2..8);
+        let g = random_graph(&mut rng, n);
+
+        let size = rng.gen_range(0..=n);
+        let vertices = sample(&mut rng, n, size).into_vec();
+        let (a, b) = subgraph_complement(&g, &vertices);
+
+        let expected = g.to_tensor4();
+        let (ta, tb) = (a.to_tensor4(), b.to_tensor4());
+        for ((e, x), y) in expected.iter().zip(ta.iter()).zip(tb.iter()) {
+            let diff = e.float_value() - (x.float_value() + y.float_value());
+            assert!(diff.norm() < 1e-9, "terms do not sum to the original on {:?}", vertices);
+        }
+    }
+}